@@ -0,0 +1,96 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::model::Error;
+
+/// `Interaction` is a single recorded request and the body of its response.
+#[derive(Serialize, Deserialize)]
+pub struct Interaction {
+    request: String,
+    body: String,
+}
+
+/// `Cassette` records the responses a `Client` receives to a file, or replays previously recorded
+/// responses instead of sending requests. Requests are matched on their method and url, which
+/// includes the search query or update id. Requests that match more than one recorded
+/// interaction, such as the pages of a search, are replayed in the order they were recorded.
+/// The file holds one JSON interaction per line, so each recording is appended to it.
+pub enum Cassette {
+    Record {
+        file: Mutex<File>,
+    },
+    Replay {
+        interactions: Mutex<HashMap<String, VecDeque<String>>>,
+    },
+}
+
+impl Cassette {
+    /// `record_to` creates a cassette that records interactions to the file at `path`, replacing
+    /// the file if it exists.
+    pub fn record_to(path: &Path) -> Result<Self, Error> {
+        let file = File::create(path).map_err(|e| {
+            Error::Internal(format!("Failed to create cassette '{}': {}", path.display(), e))
+        })?;
+        Ok(Cassette::Record {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// `load` creates a cassette that replays the interactions recorded in the file at `path`.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let data = std::fs::read_to_string(path).map_err(|e| {
+            Error::Internal(format!("Failed to read cassette '{}': {}", path.display(), e))
+        })?;
+        let mut interactions: HashMap<String, VecDeque<String>> = HashMap::new();
+        for line in data.lines().filter(|l| !l.trim().is_empty()) {
+            let i: Interaction = serde_json::from_str(line).map_err(|e| {
+                Error::Internal(format!("Failed to parse cassette '{}': {}", path.display(), e))
+            })?;
+            interactions.entry(i.request).or_default().push_back(i.body);
+        }
+        Ok(Cassette::Replay {
+            interactions: Mutex::new(interactions),
+        })
+    }
+
+    /// `replay` returns the next recorded response body for the request. It returns `None` if
+    /// the cassette is recording.
+    pub fn replay(&self, request: &str) -> Option<Result<String, Error>> {
+        match self {
+            Cassette::Record { .. } => None,
+            Cassette::Replay { interactions } => Some(
+                interactions
+                    .lock()
+                    .map_err(|e| Error::Internal(format!("Failed to lock cassette: {}", e)))
+                    .and_then(|mut i| {
+                        i.get_mut(request).and_then(|b| b.pop_front()).ok_or(Error::Internal(
+                            format!("No recorded interaction for request '{}'", request),
+                        ))
+                    }),
+            ),
+        }
+    }
+
+    /// `record` appends the interaction to the cassette file. It does nothing if the cassette is
+    /// replaying. The recording is best effort, so a request is not failed because it could not
+    /// be recorded, and the interaction is left out of the cassette instead.
+    pub fn record(&self, request: &str, body: &str) {
+        let Cassette::Record { file } = self else {
+            return;
+        };
+        let interaction = Interaction {
+            request: request.to_string(),
+            body: body.to_string(),
+        };
+        let Ok(mut line) = serde_json::to_string(&interaction) else {
+            return;
+        };
+        line.push('\n');
+        if let Ok(mut f) = file.lock() {
+            let _ = f.write_all(line.as_bytes());
+        }
+    }
+}
@@ -1,20 +1,62 @@
 #[cfg(not(feature = "blocking"))]
 use async_trait::async_trait;
+#[cfg(not(feature = "blocking"))]
+use futures::StreamExt;
 #[cfg(feature = "blocking")]
-use reqwest::blocking::RequestBuilder;
+use reqwest::blocking::{RequestBuilder, Response};
 #[cfg(not(feature = "blocking"))]
-use reqwest::RequestBuilder;
+use reqwest::{RequestBuilder, Response};
+use std::cmp::Reverse;
+#[cfg(feature = "blocking")]
+use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+#[cfg(feature = "cassette")]
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use url::Url;
-use crate::model::{Error, SearchPageMeta, SearchResult, Update};
-use crate::parser::{parse_search_results, parse_update_details};
+#[cfg(feature = "cassette")]
+use crate::cassette::Cassette;
+use crate::limiter::RequestLimiter;
+use crate::model::{
+    compare_kb, Error, PartialUpdate, RequestStats, ResolvedDownload, ResponseInfo, SearchFormFields, SearchPageMeta, SearchResult, SupersededByUpdate, Timing, Update, UpdateFields, UpdateId, UpdateSummary,
+};
+use crate::parser::{decode_html, is_blank_update_page, parse_search_page_meta, parse_search_results, parse_update_details, parse_update_fields, parse_update_summary};
 
 const LIB_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// `BATCH_CONCURRENCY` is the number of requests the batch methods run at the same time.
+#[cfg(not(feature = "blocking"))]
+const BATCH_CONCURRENCY: usize = 4;
+
+/// `DEFAULT_MAX_RESPONSE_BYTES` is the default limit on the size of a response body. Catalog pages
+/// are large because of their view state, but are far below this limit.
+const DEFAULT_MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+
+/// `ResponseHook` is called with every response the client receives from the catalog.
+pub type ResponseHook = Arc<dyn Fn(&ResponseInfo) + Send + Sync>;
+
+/// `SELF_TEST_UPDATE_ID` is the update `self_test` retrieves, the MS08-067 security update for
+/// Windows XP x64 Edition, which is not expected to change.
+const SELF_TEST_UPDATE_ID: &str = "9602ca4a-80a7-4d73-94c3-0088fcb5bce3";
+
+/// `CATALOG_RESULT_LIMIT` is the maximum number of results the catalog returns for a search, 40
+/// pages of 25 results. Pages past the limit are not served reliably.
+const CATALOG_RESULT_LIMIT: usize = 1000;
+
 /// `SearchResultsStream` represents an stream of update pages returned from a search.
 pub struct SearchResultsStream {
     client: Client,
     query: String,
     meta: SearchPageMeta,
+    // the buffered page and its HTML
+    peeked: Option<Option<(Vec<SearchResult>, String)>>,
+    failed_page_meta: Option<SearchPageMeta>,
+    deadline: Option<Instant>,
+    deadline_reached: bool,
+    result_cap: usize,
+    results_received: usize,
+    result_cap_reached: bool,
 }
 
 #[cfg(not(feature = "blocking"))]
@@ -29,25 +71,61 @@ pub trait SearchResultsStreamer {
 }
 
 impl SearchResultsStream {
-    fn new(meta: SearchPageMeta, query: &str) -> Result<Self, Error> {
+    fn new(client: Client, meta: SearchPageMeta, query: &str) -> Result<Self, Error> {
         Ok(SearchResultsStream {
-            client: Client::new()?,
+            client,
             query: query.to_string(),
             meta,
+            peeked: None,
+            failed_page_meta: None,
+            deadline: None,
+            deadline_reached: false,
+            result_cap: CATALOG_RESULT_LIMIT,
+            results_received: 0,
+            result_cap_reached: false,
         })
     }
 
-    /// `result_count` returns the total number of results for the search.
+    /// `with_deadline` sets a wall-clock deadline for the whole stream. Once the deadline has
+    /// passed, `next` returns `Ok(None)` instead of requesting further pages, even if more pages
+    /// exist, and `deadline_reached` returns true. Each request is still bounded by the client
+    /// timeout, so a page requested before the deadline can finish after it.
+    ///
+    /// # Parameters
+    ///
+    /// * `deadline` - The instant after which no further pages are requested.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// `deadline_reached` returns true if the stream ended because its deadline passed.
+    pub fn deadline_reached(&self) -> bool {
+        self.deadline_reached
+    }
+
+    /// `result_count` returns the total number of results for the search across all pages. It
+    /// is only meaningful after the first call to `next` and returns 0 until then.
     pub fn result_count(&self) -> i16 {
         self.meta.pagination.result_count
     }
 
-    /// `page_count` returns the total number of pages for the search.
+    /// `page_count` returns the total number of pages for the search. It is only meaningful after
+    /// the first call to `next` and returns 0 until then.
     pub fn page_count(&self) -> i16 {
         self.meta.pagination.page_count
     }
 
-    /// `current_page` returns the current page number for the search.
+    /// `page_size` returns the number of results per page for the search. The Microsoft Update
+    /// Catalog always returns 25 results per page and does not honor a page size control in the
+    /// search form or query string, so this is not configurable. It returns 0 until the first page
+    /// has been retrieved.
+    pub fn page_size(&self) -> i16 {
+        self.meta.pagination.page_size
+    }
+
+    /// `current_page` returns the number of the last page retrieved, or 0 before the first call
+    /// to `next`.
     pub fn current_page(&self) -> i16 {
         self.meta.pagination.current_page
     }
@@ -58,14 +136,88 @@ impl SearchResultsStream {
         self.meta.pagination.too_many_results
     }
 
-    /// `has_next_page` returns true if there are more pages of results to retrieve.
+    /// `has_next_page` returns true if there are more pages of results to retrieve, including a
+    /// page buffered by `peek_next`. It returns false before the first page is retrieved, as
+    /// whether the search has any results is not known until then.
     pub fn has_next_page(&self) -> bool {
-        self.meta.pagination.has_next_page
+        matches!(self.peeked, Some(Some(_))) || self.meta.pagination.has_next_page
+    }
+
+    /// `skip_failed_page` lets the stream continue after a page of results failed to parse. The
+    /// metadata of the failed page is used to request the page after it on the next call to
+    /// `next`. It returns false if the stream can't continue, for example when the catalog
+    /// returned an error page or the search session expired, in which case the stream stays
+    /// ended. Requests that fail before a page is received do not end the stream and can be
+    /// retried by calling `next` again.
+    pub fn skip_failed_page(&mut self) -> bool {
+        match self.failed_page_meta.take() {
+            Some(meta) => {
+                self.apply_page_meta(meta);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// `with_result_cap` lowers the number of results the stream retrieves before it stops. The
+    /// stream always stops once it has received the 1000 results the catalog returns for a
+    /// search, as the catalog does not reliably serve pages past that limit, so a cap above 1000
+    /// has no effect. Once the cap is reached, `next` returns `Ok(None)` instead of requesting
+    /// further pages and `result_cap_reached` returns true. Whole pages are retrieved, so the
+    /// stream can return up to a page more results than the cap.
+    ///
+    /// # Parameters
+    ///
+    /// * `cap` - The number of results after which no further pages are requested.
+    pub fn with_result_cap(mut self, cap: usize) -> Self {
+        self.result_cap = cap.min(CATALOG_RESULT_LIMIT);
+        self
+    }
+
+    /// `result_cap_reached` returns true if the stream ended because it received as many results
+    /// as its result cap, or the 1000 results the catalog returns for a search.
+    pub fn result_cap_reached(&self) -> bool {
+        self.result_cap_reached
+    }
+
+    // has_page_to_fetch returns true if the first page has not been retrieved yet or the last page
+    // retrieved links a next page.
+    fn has_page_to_fetch(&self) -> bool {
+        !self.meta.pagination.initialized || self.meta.pagination.has_next_page
+    }
+
+    // check_result_cap marks the stream as ended if it has received as many results as its cap.
+    fn check_result_cap(&mut self) -> bool {
+        if self.results_received >= self.result_cap {
+            self.result_cap_reached = true;
+        }
+        self.result_cap_reached
+    }
+
+    // check_deadline marks the stream as ended if its deadline has passed.
+    fn check_deadline(&mut self) -> bool {
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            self.deadline_reached = true;
+        }
+        self.deadline_reached
+    }
+
+    fn apply_page_meta(&mut self, meta: SearchPageMeta) {
+        self.meta.event_target = meta.event_target;
+        self.meta.event_argument = meta.event_argument;
+        self.meta.event_validation = meta.event_validation;
+        self.meta.view_state = meta.view_state;
+        self.meta.view_state_generator = meta.view_state_generator;
+        self.meta.pagination = meta.pagination;
     }
 
-    fn process_search_page(&mut self, html: String) -> Result<Option<Vec<SearchResult>>, Error> {
-        let page = parse_search_results(&html).map_err(|e| {
+    fn process_search_page(&mut self, html: &str) -> Result<Option<Vec<SearchResult>>, Error> {
+        self.failed_page_meta = None;
+        self.meta.pagination.initialized = true;
+        let fields = &self.client.config.search_form_fields;
+        let page = parse_search_results(html, fields).map_err(|e| {
             self.meta.pagination.has_next_page = false;
+            self.failed_page_meta = parse_search_page_meta(html, fields).ok();
             Error::Search(format!(
                 "Failed to parse search results for {}: {:?}",
                 self.query, e
@@ -73,13 +225,8 @@ impl SearchResultsStream {
         })?;
         match page {
             Some(p) => {
-                self.meta.event_target = p.0.event_target;
-                self.meta.event_argument = p.0.event_argument;
-                self.meta.event_validation = p.0.event_validation;
-                self.meta.view_state = p.0.view_state;
-                self.meta.view_state_generator = p.0.view_state_generator;
-                self.meta.pagination.has_next_page = p.0.pagination.has_next_page;
-                self.meta.pagination.too_many_results = p.0.pagination.too_many_results;
+                self.apply_page_meta(p.0);
+                self.results_received += p.1.len();
                 Ok(Some(p.1))
             }
             None => {
@@ -88,45 +235,355 @@ impl SearchResultsStream {
             }
         }
     }
+
+    // peeked_results returns a copy of the results of the buffered page.
+    fn peeked_results(&self) -> Option<Vec<SearchResult>> {
+        self.peeked.as_ref().and_then(|p| p.as_ref()).map(|p| p.0.clone())
+    }
 }
 
 #[cfg(not(feature = "blocking"))]
-#[async_trait]
-impl SearchResultsStreamer for SearchResultsStream {
-    async fn next(&mut self) -> Result<Option<Vec<SearchResult>>, Error> {
-        if !self.has_next_page() {
+impl SearchResultsStream {
+    /// `peek_next` retrieves the next page of results without advancing the stream. The page is
+    /// buffered and returned by the following call to `next`, so it is only requested once.
+    pub async fn peek_next(&mut self) -> Result<Option<Vec<SearchResult>>, Error> {
+        if self.peeked.is_none() {
+            let page = self.fetch_next_page().await?;
+            self.peeked = Some(page);
+        }
+        Ok(self.peeked_results())
+    }
+
+    /// `next_with_html` retrieves the next page of results like `next`, along with the HTML of the
+    /// page the results were parsed from, for example to archive the page for reprocessing.
+    pub async fn next_with_html(&mut self) -> Result<Option<(Vec<SearchResult>, String)>, Error> {
+        if let Some(page) = self.peeked.take() {
+            return Ok(page);
+        }
+        self.fetch_next_page().await
+    }
+
+    /// `latest_per_kb` drains the stream and returns the most recently modified result for each
+    /// KB number, ordered numerically by KB number. Results with the same last modified date are tie-broken
+    /// by the lowest update id. Results without a KB number are all returned, after the others.
+    /// All pages are retrieved before returning.
+    pub async fn latest_per_kb(mut self) -> Result<Vec<SearchResult>, Error> {
+        let mut results = vec![];
+        while let Some(page) = self.next().await? {
+            results.extend(page);
+        }
+        Ok(select_latest_per_kb(results))
+    }
+
+    /// `write_csv` drains the stream and writes each search result as a CSV record to `w`,
+    /// preceded by a header row. Pages are written as they are retrieved. Dates are written in
+    /// ISO-8601 format and sizes in bytes. It returns the number of records written.
+    ///
+    /// # Parameters
+    ///
+    /// * `w` - The writer to write the CSV records to.
+    #[cfg(feature = "csv")]
+    pub async fn write_csv(mut self, w: impl std::io::Write) -> Result<usize, Error> {
+        let mut writer = csv::Writer::from_writer(w);
+        write_csv_header(&mut writer)?;
+        let mut count = 0;
+        while let Some(page) = self.next().await? {
+            count += write_csv_records(&mut writer, &page)?;
+        }
+        writer.flush().map_err(|e| Error::Export(e.to_string()))?;
+        Ok(count)
+    }
+
+    async fn fetch_next_page(&mut self) -> Result<Option<(Vec<SearchResult>, String)>, Error> {
+        if !self.has_page_to_fetch() || self.check_deadline() || self.check_result_cap() {
             return Ok(None);
         }
         let builder = self.client.get_search_builder(&self.query, &self.meta)?;
-        let resp = builder.send().await.map_err(Error::Client)?;
-        resp.error_for_status_ref()?;
-        let html = resp.text().await.map_err(Error::Client)?;
-        self.process_search_page(html)
+        let html = self.client.fetch_text(builder).await?;
+        Ok(self.process_search_page(&html)?.map(|results| (results, html)))
     }
 }
 
 #[cfg(feature = "blocking")]
-impl SearchResultsStreamer for SearchResultsStream {
-    fn next(&mut self) -> Result<Option<Vec<SearchResult>>, Error> {
-        if !self.has_next_page() {
+impl SearchResultsStream {
+    /// `peek_next` retrieves the next page of results without advancing the stream. The page is
+    /// buffered and returned by the following call to `next`, so it is only requested once.
+    pub fn peek_next(&mut self) -> Result<Option<Vec<SearchResult>>, Error> {
+        if self.peeked.is_none() {
+            let page = self.fetch_next_page()?;
+            self.peeked = Some(page);
+        }
+        Ok(self.peeked_results())
+    }
+
+    pub fn next_with_html(&mut self) -> Result<Option<(Vec<SearchResult>, String)>, Error> {
+        if let Some(page) = self.peeked.take() {
+            return Ok(page);
+        }
+        self.fetch_next_page()
+    }
+
+    /// `latest_per_kb` drains the stream and returns the most recently modified result for each
+    /// KB number, ordered numerically by KB number. Results with the same last modified date are tie-broken
+    /// by the lowest update id. Results without a KB number are all returned, after the others.
+    /// All pages are retrieved before returning.
+    pub fn latest_per_kb(mut self) -> Result<Vec<SearchResult>, Error> {
+        let mut results = vec![];
+        while let Some(page) = self.next()? {
+            results.extend(page);
+        }
+        Ok(select_latest_per_kb(results))
+    }
+
+    #[cfg(feature = "csv")]
+    pub fn write_csv(mut self, w: impl std::io::Write) -> Result<usize, Error> {
+        let mut writer = csv::Writer::from_writer(w);
+        write_csv_header(&mut writer)?;
+        let mut count = 0;
+        while let Some(page) = self.next()? {
+            count += write_csv_records(&mut writer, &page)?;
+        }
+        writer.flush().map_err(|e| Error::Export(e.to_string()))?;
+        Ok(count)
+    }
+
+    fn fetch_next_page(&mut self) -> Result<Option<(Vec<SearchResult>, String)>, Error> {
+        if !self.has_page_to_fetch() || self.check_deadline() || self.check_result_cap() {
             return Ok(None);
         }
         let builder = self.client.get_search_builder(&self.query, &self.meta)?;
-        let resp = builder.send().map_err(Error::Client)?;
-        resp.error_for_status_ref()?;
-        let html = resp.text().map_err(Error::Client)?;
-        self.process_search_page(html)
+        let html = self.client.fetch_text(builder)?;
+        Ok(self.process_search_page(&html)?.map(|results| (results, html)))
+    }
+}
+
+// select_latest_per_kb keeps the most recently modified result for each KB number, ordered
+// numerically by KB number. Results without a KB number can't be grouped, so they are all kept,
+// after the grouped results.
+fn select_latest_per_kb(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut latest: HashMap<String, SearchResult> = HashMap::new();
+    let mut without_kb = vec![];
+    for r in results {
+        let Some(kb) = r.kb.clone() else {
+            without_kb.push(r);
+            continue;
+        };
+        match latest.get(&kb) {
+            Some(l) if (l.last_modified, Reverse(&l.id)) >= (r.last_modified, Reverse(&r.id)) => {}
+            _ => {
+                latest.insert(kb, r);
+            }
+        }
+    }
+    let mut grouped: Vec<(String, SearchResult)> = latest.into_iter().collect();
+    grouped.sort_by(|a, b| compare_kb(&a.0, &b.0));
+    grouped.into_iter().map(|(_, r)| r).chain(without_kb).collect()
+}
+
+#[cfg(feature = "csv")]
+fn write_csv_header<W: std::io::Write>(writer: &mut csv::Writer<W>) -> Result<(), Error> {
+    writer
+        .write_record([
+            "title",
+            "id",
+            "kb",
+            "product",
+            "classification",
+            "last_modified",
+            "version",
+            "size",
+        ])
+        .map_err(|e| Error::Export(e.to_string()))
+}
+
+#[cfg(feature = "csv")]
+fn write_csv_records<W: std::io::Write>(
+    writer: &mut csv::Writer<W>,
+    results: &[SearchResult],
+) -> Result<usize, Error> {
+    for r in results {
+        writer
+            .write_record([
+                r.title.as_str(),
+                r.id.as_str(),
+                r.kb.as_deref().unwrap_or_default(),
+                r.product.as_str(),
+                r.classification.as_str(),
+                r.last_modified.format("%Y-%m-%d").to_string().as_str(),
+                r.version.as_deref().unwrap_or_default(),
+                r.size.to_string().as_str(),
+            ])
+            .map_err(|e| Error::Export(e.to_string()))?;
+    }
+    Ok(results.len())
+}
+
+#[cfg(not(feature = "blocking"))]
+#[async_trait]
+impl SearchResultsStreamer for SearchResultsStream {
+    async fn next(&mut self) -> Result<Option<Vec<SearchResult>>, Error> {
+        Ok(self.next_with_html().await?.map(|page| page.0))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl SearchResultsStreamer for SearchResultsStream {
+    fn next(&mut self) -> Result<Option<Vec<SearchResult>>, Error> {
+        Ok(self.next_with_html()?.map(|page| page.0))
+    }
+}
+
+/// `Catalog` abstracts the operations of the Microsoft Update Catalog so code depending on it can
+/// be tested with canned data instead of the network. `Client` is the implementation backed by
+/// the catalog itself.
+#[cfg(not(feature = "blocking"))]
+#[async_trait]
+pub trait Catalog {
+    /// `Stream` is the stream of result pages returned by `search`.
+    type Stream: SearchResultsStreamer + Send;
+
+    /// `search` starts a search of the catalog for the given query.
+    fn search(&self, query: &str) -> Result<Self::Stream, Error>;
+
+    /// `get_update` retrieves the update details for the given update id.
+    async fn get_update(&self, update_id: &UpdateId) -> Result<Update, Error>;
+
+    /// `resolve_download_redirect` follows the redirects of the given download url.
+    async fn resolve_download_redirect(&self, url: &Url) -> Result<ResolvedDownload, Error>;
+}
+
+#[cfg(feature = "blocking")]
+pub trait Catalog {
+    type Stream: SearchResultsStreamer;
+
+    fn search(&self, query: &str) -> Result<Self::Stream, Error>;
+
+    fn get_update(&self, update_id: &UpdateId) -> Result<Update, Error>;
+
+    fn resolve_download_redirect(&self, url: &Url) -> Result<ResolvedDownload, Error>;
+}
+
+#[cfg(not(feature = "blocking"))]
+#[async_trait]
+impl Catalog for Client {
+    type Stream = SearchResultsStream;
+
+    fn search(&self, query: &str) -> Result<Self::Stream, Error> {
+        Client::search(self, query)
+    }
+
+    async fn get_update(&self, update_id: &UpdateId) -> Result<Update, Error> {
+        Client::get_update(self, update_id).await
+    }
+
+    async fn resolve_download_redirect(&self, url: &Url) -> Result<ResolvedDownload, Error> {
+        Client::resolve_download_redirect(self, url).await
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Catalog for Client {
+    type Stream = SearchResultsStream;
+
+    fn search(&self, query: &str) -> Result<Self::Stream, Error> {
+        Client::search(self, query)
+    }
+
+    fn get_update(&self, update_id: &UpdateId) -> Result<Update, Error> {
+        Client::get_update(self, update_id)
+    }
+
+    fn resolve_download_redirect(&self, url: &Url) -> Result<ResolvedDownload, Error> {
+        Client::resolve_download_redirect(self, url)
     }
 }
 
 /// `Client` represents a client for the Microsoft Update Catalog.
+#[derive(Clone)]
 pub struct Client {
     #[cfg(feature = "blocking")]
     client: reqwest::blocking::Client,
     #[cfg(not(feature = "blocking"))]
     client: reqwest::Client,
-    search_url: String,
-    update_url: String,
+    config: ClientConfig,
+    // shared between clones so the limit applies to all of them
+    limiter: Option<Arc<RequestLimiter>>,
+    on_response: Option<ResponseHook>,
+    #[cfg(feature = "cassette")]
+    cassette: Option<Arc<Cassette>>,
+}
+
+/// `ClientConfig` represents the settings a `Client` uses, as configured with `ClientBuilder`.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct ClientConfig {
+    /// `search_url` is the url of the catalog search page.
+    pub search_url: String,
+    /// `update_url` is the url of the update details page, which the update id is appended to.
+    pub update_url: String,
+    /// `download_dialog_url` is the url of the catalog download dialog.
+    pub download_dialog_url: String,
+    /// `user_agent` is the `User-Agent` header sent with every request.
+    pub user_agent: String,
+    /// `compression` is true if the client accepts gzip and brotli compressed responses.
+    pub compression: bool,
+    /// `max_retries` is the number of times a throttled request is retried.
+    pub max_retries: u32,
+    /// `retry_backoff` is the delay before retrying a throttled request when the response does not
+    /// include a `Retry-After` header.
+    pub retry_backoff: Duration,
+    /// `max_retry_delay` is the longest the client waits before retrying a throttled request, even
+    /// when the `Retry-After` header asks for a longer delay.
+    pub max_retry_delay: Duration,
+    /// `pool_max_idle_per_host` is the maximum number of idle connections kept open to each host.
+    pub pool_max_idle_per_host: usize,
+    /// `pool_idle_timeout` is how long an idle connection is kept open, or `None` to keep idle
+    /// connections open indefinitely.
+    pub pool_idle_timeout: Option<Duration>,
+    /// `cookie_store` is true if cookies set by the catalog are stored and sent with later
+    /// requests.
+    pub cookie_store: bool,
+    /// `max_concurrent_requests` is the maximum number of requests in flight, or `None` if
+    /// requests are not limited.
+    pub max_concurrent_requests: Option<usize>,
+    /// `retry_blank_updates` is true if the update details methods request a details page again
+    /// when the catalog serves it with a blank title or update id.
+    pub retry_blank_updates: bool,
+    /// `max_response_bytes` is the maximum size of a decompressed response body.
+    pub max_response_bytes: usize,
+    /// `search_form_fields` are the names of the form fields posted to request the next page of
+    /// search results.
+    pub search_form_fields: SearchFormFields,
+}
+
+impl Default for ClientConfig {
+    /// `default` creates a `ClientConfig` with the default settings of `ClientBuilder`.
+    fn default() -> Self {
+        ClientConfig {
+            search_url: String::from("https://www.catalog.update.microsoft.com/Search.aspx"),
+            // ScopedViewInline.aspx is already the lightweight details page. It is the fragment the
+            // catalog loads into the details popup, without the site chrome of ScopedView.aspx. The
+            // catalog offers no smaller representation, such as a print view or JSON endpoint.
+            update_url: String::from(
+                "https://www.catalog.update.microsoft.com/ScopedViewInline.aspx?updateid=",
+            ),
+            download_dialog_url: String::from(
+                "https://www.catalog.update.microsoft.com/DownloadDialog.aspx",
+            ),
+            user_agent: format!("msuc-rs/{}", LIB_VERSION),
+            compression: true,
+            max_retries: 0,
+            retry_backoff: Duration::from_secs(5),
+            max_retry_delay: Duration::from_secs(60),
+            // the reqwest defaults
+            pool_max_idle_per_host: usize::MAX,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            cookie_store: true,
+            max_concurrent_requests: None,
+            retry_blank_updates: false,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            search_form_fields: SearchFormFields::default(),
+        }
+    }
 }
 
 impl Default for Client {
@@ -138,135 +595,619 @@ impl Default for Client {
     }
 }
 
-impl Client {
-    /// `new` creates a new MSUC `Client` with default values.
-    /// The client does not support non-async operation at this time.
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use msuc::prelude::*;
-    /// let msuc_client = MsucClient::new().expect("Failed to create MSUC client");
-    /// ```
-    pub fn new() -> Result<Self, Error> {
-        #[cfg(not(feature = "blocking"))]
-            let client = reqwest::Client::builder()
-            .user_agent(format!("msuc-rs/{}", LIB_VERSION))
-            .build()
-            .map_err(Error::Client)?;
-        #[cfg(feature = "blocking")]
-            let client = reqwest::blocking::Client::builder()
-            .user_agent(format!("msuc-rs/{}", LIB_VERSION))
-            .build()
-            .map_err(Error::Client)?;
+/// `BROWSER_USER_AGENT` is a desktop browser user agent that the Microsoft Update Catalog serves
+/// the same pages to as an interactive visitor.
+const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36 Edg/118.0.2088.46";
 
-        Ok(Client {
-            client,
-            search_url: String::from("https://www.catalog.update.microsoft.com/Search.aspx"),
-            update_url: String::from(
-                "https://www.catalog.update.microsoft.com/ScopedViewInline.aspx?updateid=",
-            ),
-        })
-    }
+/// `ClientBuilder` configures and creates a MSUC `Client`.
+pub struct ClientBuilder {
+    config: ClientConfig,
+    on_response: Option<ResponseHook>,
+    #[cfg(feature = "cassette")]
+    record_to: Option<PathBuf>,
+    #[cfg(feature = "cassette")]
+    replay_from: Option<PathBuf>,
+}
 
-    fn get_search_builder(
-        &self,
-        query: &str,
-        meta: &SearchPageMeta,
-    ) -> Result<RequestBuilder, Error> {
-        let mut u = Url::parse(&self.search_url).map_err(|e| {
-            Error::Internal(format!(
-                "Failed to parse search url '{}': {:?}",
-                self.search_url,
-                e
-            ))
-        })?;
-        u.set_query(Some(&format!("q={}", query)));
-        match meta.event_target.as_str() {
-            "" => Ok(self.client.get(u.as_str())),
-            _ => Ok(self.client.post(u.as_str()).form(&meta.as_map())),
+impl Default for ClientBuilder {
+    /// `default` creates a new `ClientBuilder` with the `msuc-rs/{version}` user agent.
+    fn default() -> Self {
+        ClientBuilder {
+            config: ClientConfig::default(),
+            on_response: None,
+            #[cfg(feature = "cassette")]
+            record_to: None,
+            #[cfg(feature = "cassette")]
+            replay_from: None,
         }
     }
+}
+
+impl ClientBuilder {
+    /// `new` creates a new `ClientBuilder` with default values.
+    pub fn new() -> Self {
+        ClientBuilder::default()
+    }
 
-    /// `search` returns a stream to receive pages of search results from
-    /// the Microsoft Update Catalog. Calling `next` on the stream will return a `Result`
-    /// containing either a `Vec<SearchResult>` or `None` if there are no more pages.
+    /// `user_agent` sets the user agent sent with every request.
     ///
     /// # Parameters
     ///
-    /// * `query` - The search query to use.
+    /// * `user_agent` - The user agent to use.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.config.user_agent = user_agent.to_string();
+        self
+    }
+
+    /// `browser_user_agent` sets the user agent to a desktop browser user agent. The catalog has
+    /// been seen returning empty results to non-browser agents, so this trades identifying the
+    /// client honestly for reliability. Prefer the default user agent unless you are affected.
+    pub fn browser_user_agent(self) -> Self {
+        self.user_agent(BROWSER_USER_AGENT)
+    }
+
+    /// `compression` sets whether gzip and brotli compressed responses are requested. Responses
+    /// are decompressed transparently before they are parsed. Compression is enabled by default.
     ///
-    /// # Example
+    /// # Parameters
     ///
-    /// ```
-    /// use msuc::prelude::*;
-    /// use tokio_test;
+    /// * `enabled` - Whether to request compressed responses.
+    pub fn compression(mut self, enabled: bool) -> Self {
+        self.config.compression = enabled;
+        self
+    }
+
+    /// `max_retries` sets how many times a request is retried when the catalog throttles it with a
+    /// `429 Too Many Requests` or `503 Service Unavailable` response. Requests are not retried by
+    /// default.
     ///
-    /// #[cfg(not(feature = "blocking"))]
-    /// tokio_test::block_on(async {
-    ///     let msuc_client = MsucClient::new().expect("Failed to create MSUC client");
-    ///     let mut stream = msuc_client.search("MS08-067").expect("Failed to create search stream");
-    ///     loop {
-    ///         match stream.next().await {
-    ///             Ok(Some(sr)) => {
-    ///                 for r in sr {
-    ///                     println!("{}: {}", r.id, r.title);
-    ///                 }
-    ///             }
-    ///             Ok(None) => break,
-    ///             Err(e) => {
-    ///                 println!("Error: {:?}", e);
-    ///             }
-    ///         }
-    ///     }
-    /// });
-    /// ```
+    /// # Parameters
     ///
-    /// ```
-    /// use msuc::prelude::*;
-    /// use tokio_test;
+    /// * `max_retries` - The maximum number of retries for a request.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.config.max_retries = max_retries;
+        self
+    }
+
+    /// `retry_backoff` sets how long to wait before retrying a throttled request when the response
+    /// does not include a `Retry-After` header. When the header is present, the delay the server
+    /// advertised is used instead, up to `max_retry_delay`. The default is 5 seconds.
     ///
-    /// #[cfg(feature = "blocking")]
-    /// {
-    ///     let msuc_client = MsucClient::new().expect("Failed to create MSUC client");
-    ///     let mut stream = msuc_client.search("MS08-067").expect("Failed to create search stream");
-    ///     loop {
-    ///         match stream.next() {
-    ///             Ok(Some(sr)) => {
-    ///                 for r in sr {
-    ///                     println!("{}: {}", r.id, r.title);
-    ///                 }
-    ///             }
-    ///             Ok(None) => break,
-    ///             Err(e) => {
-    ///                 println!("Error: {:?}", e);
-    ///             }
-    ///         }
-    ///     }
-    /// };
-    /// ```
-    pub fn search(&self, query: &str) -> Result<SearchResultsStream, Error> {
-        SearchResultsStream::new(SearchPageMeta::default(), query)
+    /// # Parameters
+    ///
+    /// * `retry_backoff` - The delay between retries.
+    pub fn retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.config.retry_backoff = retry_backoff;
+        self
     }
 
-    /// `get_update` retrieves the update details for the given update id.
-    /// The update id can be found in the `id` field of the `SearchResult` struct.
+    /// `max_retry_delay` sets the longest the client waits before retrying a throttled request. A
+    /// longer delay advertised by the `Retry-After` header is shortened to this. The default is 60
+    /// seconds.
     ///
     /// # Parameters
     ///
-    /// * `update_id` - The update id to retrieve details for.
+    /// * `max_retry_delay` - The maximum delay before a retry.
+    pub fn max_retry_delay(mut self, max_retry_delay: Duration) -> Self {
+        self.config.max_retry_delay = max_retry_delay;
+        self
+    }
+
+    /// `pool_max_idle_per_host` sets the maximum number of idle connections kept open to the
+    /// catalog for reuse. The number is unlimited by default. A respectful crawler does not need
+    /// more idle connections than the number of requests it runs at the same time, which is four
+    /// for the batch methods of `Client`.
     ///
-    /// # Example
+    /// # Parameters
     ///
-    /// ```
+    /// * `max` - The maximum number of idle connections per host.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.config.pool_max_idle_per_host = max;
+        self
+    }
+
+    /// `pool_idle_timeout` sets how long an idle connection is kept open for reuse before it is
+    /// closed. The default is 90 seconds, which is enough to reuse connections across the pages
+    /// of a search without holding them open between crawls.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - How long to keep idle connections open.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.config.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// `cookie_store` sets whether cookies set by the catalog are kept and sent with later
+    /// requests. The catalog's pagination is tied to an ASP.NET session cookie as well as the view
+    /// state, so the pages of a search stream are more reliable when the session cookie is kept.
+    /// The cookie store is shared by every clone of the client. It is enabled by default.
+    ///
+    /// # Parameters
+    ///
+    /// * `enabled` - Whether to keep cookies between requests.
+    pub fn cookie_store(mut self, enabled: bool) -> Self {
+        self.config.cookie_store = enabled;
+        self
+    }
+
+    /// `max_concurrent_requests` sets the maximum number of requests in flight to the catalog at
+    /// the same time, across every method and every clone of the built `Client`. Requests past
+    /// the limit wait until an earlier request completes. Requests are not limited by default.
+    ///
+    /// # Parameters
+    ///
+    /// * `max` - The maximum number of requests in flight, at least 1.
+    pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+        self.config.max_concurrent_requests = Some(max.max(1));
+        self
+    }
+
+    /// `retry_blank_updates` sets whether the update details methods, such as `get_update` and
+    /// `summarize`, request a details page once more when the catalog serves it half-rendered,
+    /// with a blank title or update id, which a second request usually resolves. An error is
+    /// returned if the second page is blank too. Blank pages are not retried by default.
+    ///
+    /// # Parameters
+    ///
+    /// * `enabled` - Whether to retry blank details pages.
+    pub fn retry_blank_updates(mut self, enabled: bool) -> Self {
+        self.config.retry_blank_updates = enabled;
+        self
+    }
+
+    /// `max_response_bytes` sets the maximum size of a response body, after it is decompressed.
+    /// The body is read in chunks and the request fails with `Error::ResponseTooLarge` as soon as
+    /// it exceeds the limit, bounding the memory used for a broken or malicious response. The
+    /// default is 64 MiB.
+    ///
+    /// # Parameters
+    ///
+    /// * `max` - The maximum number of bytes in a response body.
+    pub fn max_response_bytes(mut self, max: usize) -> Self {
+        self.config.max_response_bytes = max;
+        self
+    }
+
+    /// `search_form_fields` sets the names of the form fields posted to request the next page of
+    /// search results, and the next page link control posted as the event target. The defaults
+    /// are the names the catalog currently uses, listed on `SearchFormFields`. They only need to
+    /// be changed if the catalog renames its controls.
+    ///
+    /// # Parameters
+    ///
+    /// * `fields` - The names of the search form fields.
+    pub fn search_form_fields(mut self, fields: SearchFormFields) -> Self {
+        self.config.search_form_fields = fields;
+        self
+    }
+
+    /// `on_response` sets a function that is called with every response the client receives from
+    /// the catalog, including throttled responses that are retried and unsuccessful responses,
+    /// for example to log requests or collect metrics. Responses replayed from a cassette are not
+    /// received from the catalog and are not passed to it. No function is called by default.
+    ///
+    /// # Parameters
+    ///
+    /// * `hook` - The function to call with each response.
+    pub fn on_response(mut self, hook: ResponseHook) -> Self {
+        self.on_response = Some(hook);
+        self
+    }
+
+    /// `record_to` records the response to every request the client makes to a cassette file at
+    /// `path`, which can be replayed with `replay_from`. Each response is appended to the file as
+    /// it is received, and a response that fails to be written is left out of the cassette rather
+    /// than failing the request. Only the responses to search and update requests are recorded.
+    /// This replaces any cassette set with `replay_from`.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - The path of the cassette file to write.
+    #[cfg(feature = "cassette")]
+    pub fn record_to(mut self, path: impl AsRef<Path>) -> Self {
+        self.record_to = Some(path.as_ref().to_path_buf());
+        self.replay_from = None;
+        self
+    }
+
+    /// `replay_from` replays the responses recorded to the cassette file at `path` with
+    /// `record_to` instead of sending requests. The rest of the builder configuration applies as
+    /// it does to a client sending requests, so a cassette should be replayed with the settings it
+    /// was recorded with. An error is returned for any request that was not recorded. This
+    /// replaces any cassette set with `record_to`.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - The path of the cassette file to replay.
+    #[cfg(feature = "cassette")]
+    pub fn replay_from(mut self, path: impl AsRef<Path>) -> Self {
+        self.replay_from = Some(path.as_ref().to_path_buf());
+        self.record_to = None;
+        self
+    }
+
+    /// `build` creates the MSUC `Client` from the builder configuration.
+    pub fn build(self) -> Result<Client, Error> {
+        #[cfg(not(feature = "blocking"))]
+            let client = reqwest::Client::builder()
+            .user_agent(self.config.user_agent.as_str())
+            .gzip(self.config.compression)
+            .brotli(self.config.compression)
+            .pool_max_idle_per_host(self.config.pool_max_idle_per_host)
+            .pool_idle_timeout(self.config.pool_idle_timeout)
+            .cookie_store(self.config.cookie_store)
+            .build()
+            .map_err(Error::Client)?;
+        #[cfg(feature = "blocking")]
+            let client = reqwest::blocking::Client::builder()
+            .user_agent(self.config.user_agent.as_str())
+            .gzip(self.config.compression)
+            .brotli(self.config.compression)
+            .pool_max_idle_per_host(self.config.pool_max_idle_per_host)
+            .pool_idle_timeout(self.config.pool_idle_timeout)
+            .cookie_store(self.config.cookie_store)
+            .build()
+            .map_err(Error::Client)?;
+        #[cfg(feature = "cassette")]
+        let cassette = match (&self.replay_from, &self.record_to) {
+            (Some(p), _) => Some(Arc::new(Cassette::load(p)?)),
+            (None, Some(p)) => Some(Arc::new(Cassette::record_to(p)?)),
+            (None, None) => None,
+        };
+
+        Ok(Client {
+            client,
+            limiter: self.config.max_concurrent_requests.map(|m| Arc::new(RequestLimiter::new(m))),
+            config: self.config,
+            on_response: self.on_response,
+            #[cfg(feature = "cassette")]
+            cassette,
+        })
+    }
+}
+
+impl Client {
+    /// `new` creates a new MSUC `Client` with default values.
+    /// The client does not support non-async operation at this time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use msuc::prelude::*;
+    /// let msuc_client = MsucClient::new().expect("Failed to create MSUC client");
+    /// ```
+    pub fn new() -> Result<Self, Error> {
+        ClientBuilder::new().build()
+    }
+
+    /// `from_cassette` creates a new MSUC `Client` with default values that replays the responses
+    /// recorded to the cassette file at `path` instead of sending requests. Use
+    /// `ClientBuilder::replay_from` to replay a cassette recorded with other settings.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - The path of the cassette file to replay.
+    #[cfg(feature = "cassette")]
+    pub fn from_cassette(path: impl AsRef<Path>) -> Result<Self, Error> {
+        ClientBuilder::new().replay_from(path).build()
+    }
+
+    /// `builder` returns a `ClientBuilder` to configure a new MSUC `Client`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use msuc::prelude::*;
+    /// let msuc_client = MsucClient::builder()
+    ///     .browser_user_agent()
+    ///     .pool_max_idle_per_host(4)
+    ///     .build()
+    ///     .expect("Failed to create MSUC client");
+    /// ```
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// `config` returns the settings the client uses, such as the catalog urls, user agent, and
+    /// retry and concurrency settings, to confirm how a client was configured.
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// `send` sends the request, retrying it when the catalog throttles the request and retries
+    /// are enabled. It returns an error for any unsuccessful response.
+    #[cfg(not(feature = "blocking"))]
+    async fn send(&self, builder: RequestBuilder) -> Result<Response, Error> {
+        let (resp, _, info) = self.send_with_stats(builder).await?;
+        self.notify_response(&info);
+        Ok(resp)
+    }
+
+    // send_with_stats passes retried and unsuccessful responses to the response hook, and returns
+    // the info for the final response so the caller can report it once the body has been read.
+    #[cfg(not(feature = "blocking"))]
+    async fn send_with_stats(&self, builder: RequestBuilder) -> Result<(Response, RequestStats, ResponseInfo), Error> {
+        let mut stats = RequestStats::default();
+        loop {
+            let request = builder.try_clone().ok_or(Error::Internal(
+                "Failed to clone request for retry".to_string(),
+            ))?;
+            let request = request.build().map_err(Error::Client)?;
+            let method = request.method().to_string();
+            let resp = self.client.execute(request).await.map_err(Error::Client)?;
+            stats.attempts += 1;
+            let info = get_response_info(method, &resp);
+            if stats.attempts <= self.config.max_retries && is_throttled(resp.status()) {
+                self.notify_response(&info);
+                let delay = get_retry_delay(resp.headers(), self.config.retry_backoff, self.config.max_retry_delay);
+                stats.total_wait += delay;
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+            if let Err(e) = resp.error_for_status_ref() {
+                self.notify_response(&info);
+                return Err(e.into());
+            }
+            return Ok((resp, stats, info));
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    fn send(&self, builder: RequestBuilder) -> Result<Response, Error> {
+        let (resp, _, info) = self.send_with_stats(builder)?;
+        self.notify_response(&info);
+        Ok(resp)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn send_with_stats(&self, builder: RequestBuilder) -> Result<(Response, RequestStats, ResponseInfo), Error> {
+        let mut stats = RequestStats::default();
+        loop {
+            let request = builder.try_clone().ok_or(Error::Internal(
+                "Failed to clone request for retry".to_string(),
+            ))?;
+            let request = request.build().map_err(Error::Client)?;
+            let method = request.method().to_string();
+            let resp = self.client.execute(request).map_err(Error::Client)?;
+            stats.attempts += 1;
+            let info = get_response_info(method, &resp);
+            if stats.attempts <= self.config.max_retries && is_throttled(resp.status()) {
+                self.notify_response(&info);
+                let delay = get_retry_delay(resp.headers(), self.config.retry_backoff, self.config.max_retry_delay);
+                stats.total_wait += delay;
+                std::thread::sleep(delay);
+                continue;
+            }
+            if let Err(e) = resp.error_for_status_ref() {
+                self.notify_response(&info);
+                return Err(e.into());
+            }
+            return Ok((resp, stats, info));
+        }
+    }
+
+    // notify_response passes the response to the response hook, if there is one.
+    fn notify_response(&self, info: &ResponseInfo) {
+        if let Some(hook) = &self.on_response {
+            hook(info);
+        }
+    }
+
+    /// `fetch_text` sends the request and returns the body of the response, recording or replaying
+    /// it when the client has a cassette.
+    #[cfg(not(feature = "blocking"))]
+    async fn fetch_text(&self, builder: RequestBuilder) -> Result<String, Error> {
+        Ok(self.fetch_text_with_stats(builder).await?.0)
+    }
+
+    // fetch_text_with_stats returns no attempts for a response replayed from a cassette.
+    #[cfg(not(feature = "blocking"))]
+    async fn fetch_text_with_stats(&self, builder: RequestBuilder) -> Result<(String, RequestStats), Error> {
+        #[cfg(feature = "cassette")]
+        let key = get_request_key(&builder)?;
+        #[cfg(feature = "cassette")]
+        if let Some(body) = self.cassette.as_ref().and_then(|c| c.replay(&key)) {
+            return body.map(|b| (b, RequestStats::default()));
+        }
+        let _permit = match &self.limiter {
+            Some(l) => Some(l.acquire().await?),
+            None => None,
+        };
+        let (resp, stats, mut info) = self.send_with_stats(builder).await?;
+        let content_type = get_content_type(&resp);
+        let body = read_body(resp, self.config.max_response_bytes).await;
+        if let Ok(b) = &body {
+            info.body_len = Some(b.len() as u64);
+        }
+        self.notify_response(&info);
+        let body = body?;
+        let text = decode_html(&body, content_type.as_deref());
+        #[cfg(feature = "cassette")]
+        if let Some(c) = &self.cassette {
+            c.record(&key, &text);
+        }
+        Ok((text, stats))
+    }
+
+    #[cfg(feature = "blocking")]
+    fn fetch_text(&self, builder: RequestBuilder) -> Result<String, Error> {
+        Ok(self.fetch_text_with_stats(builder)?.0)
+    }
+
+    // fetch_text_with_stats returns no attempts for a response replayed from a cassette.
+    #[cfg(feature = "blocking")]
+    fn fetch_text_with_stats(&self, builder: RequestBuilder) -> Result<(String, RequestStats), Error> {
+        #[cfg(feature = "cassette")]
+        let key = get_request_key(&builder)?;
+        #[cfg(feature = "cassette")]
+        if let Some(body) = self.cassette.as_ref().and_then(|c| c.replay(&key)) {
+            return body.map(|b| (b, RequestStats::default()));
+        }
+        let _permit = match &self.limiter {
+            Some(l) => Some(l.acquire()?),
+            None => None,
+        };
+        let (resp, stats, mut info) = self.send_with_stats(builder)?;
+        let content_type = get_content_type(&resp);
+        let body = read_body(resp, self.config.max_response_bytes);
+        if let Ok(b) = &body {
+            info.body_len = Some(b.len() as u64);
+        }
+        self.notify_response(&info);
+        let body = body?;
+        let text = decode_html(&body, content_type.as_deref());
+        #[cfg(feature = "cassette")]
+        if let Some(c) = &self.cassette {
+            c.record(&key, &text);
+        }
+        Ok((text, stats))
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    async fn get_update_html(&self, update_id: &UpdateId) -> Result<String, Error> {
+        Ok(self.get_update_html_with_stats(update_id).await?.0)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn get_update_html(&self, update_id: &UpdateId) -> Result<String, Error> {
+        Ok(self.get_update_html_with_stats(update_id)?.0)
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    async fn get_update_html_with_stats(&self, update_id: &UpdateId) -> Result<(String, RequestStats), Error> {
+        let url = format!("{}{}", self.config.update_url, update_id);
+        let (html, stats) = self.fetch_text_with_stats(self.client.get(url.as_str())).await?;
+        if !self.config.retry_blank_updates || !is_blank_update_page(&html) {
+            return Ok((html, stats));
+        }
+        let (html, retry_stats) = self.fetch_text_with_stats(self.client.get(url.as_str())).await?;
+        check_blank_update_page(update_id, &html)?;
+        Ok((html, add_request_stats(stats, retry_stats)))
+    }
+
+    #[cfg(feature = "blocking")]
+    fn get_update_html_with_stats(&self, update_id: &UpdateId) -> Result<(String, RequestStats), Error> {
+        let url = format!("{}{}", self.config.update_url, update_id);
+        let (html, stats) = self.fetch_text_with_stats(self.client.get(url.as_str()))?;
+        if !self.config.retry_blank_updates || !is_blank_update_page(&html) {
+            return Ok((html, stats));
+        }
+        let (html, retry_stats) = self.fetch_text_with_stats(self.client.get(url.as_str()))?;
+        check_blank_update_page(update_id, &html)?;
+        Ok((html, add_request_stats(stats, retry_stats)))
+    }
+
+    fn get_download_dialog_builder(&self, update_id: &UpdateId) -> Result<RequestBuilder, Error> {
+        let update_ids = format!(
+            r#"[{{"size":0,"languages":"","uidInfo":"{0}","updateID":"{0}"}}]"#,
+            update_id
+        );
+        Ok(self
+            .client
+            .post(self.config.download_dialog_url.as_str())
+            .form(&[("updateIDs", update_ids.as_str())]))
+    }
+
+    fn get_search_builder(
+        &self,
+        query: &str,
+        meta: &SearchPageMeta,
+    ) -> Result<RequestBuilder, Error> {
+        let mut u = Url::parse(&self.config.search_url).map_err(|e| {
+            Error::Internal(format!(
+                "Failed to parse search url '{}': {:?}",
+                self.config.search_url,
+                e
+            ))
+        })?;
+        u.set_query(Some(&format!("q={}", query)));
+        match meta.event_target.as_str() {
+            "" => Ok(self.client.get(u.as_str())),
+            _ => Ok(self.client.post(u.as_str()).form(&meta.as_map(&self.config.search_form_fields))),
+        }
+    }
+
+    /// `search` returns a stream to receive pages of search results from
+    /// the Microsoft Update Catalog. Calling `next` on the stream will return a `Result`
+    /// containing either a `Vec<SearchResult>` or `None` if there are no more pages.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to use.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use msuc::prelude::*;
+    /// use tokio_test;
+    ///
+    /// #[cfg(not(feature = "blocking"))]
+    /// tokio_test::block_on(async {
+    ///     let msuc_client = MsucClient::new().expect("Failed to create MSUC client");
+    ///     let mut stream = msuc_client.search("MS08-067").expect("Failed to create search stream");
+    ///     loop {
+    ///         match stream.next().await {
+    ///             Ok(Some(sr)) => {
+    ///                 for r in sr {
+    ///                     println!("{}: {}", r.id, r.title);
+    ///                 }
+    ///             }
+    ///             Ok(None) => break,
+    ///             Err(e) => {
+    ///                 println!("Error: {:?}", e);
+    ///             }
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// ```
+    /// use msuc::prelude::*;
+    /// use tokio_test;
+    ///
+    /// #[cfg(feature = "blocking")]
+    /// {
+    ///     let msuc_client = MsucClient::new().expect("Failed to create MSUC client");
+    ///     let mut stream = msuc_client.search("MS08-067").expect("Failed to create search stream");
+    ///     loop {
+    ///         match stream.next() {
+    ///             Ok(Some(sr)) => {
+    ///                 for r in sr {
+    ///                     println!("{}: {}", r.id, r.title);
+    ///                 }
+    ///             }
+    ///             Ok(None) => break,
+    ///             Err(e) => {
+    ///                 println!("Error: {:?}", e);
+    ///             }
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub fn search(&self, query: &str) -> Result<SearchResultsStream, Error> {
+        SearchResultsStream::new(self.clone(), SearchPageMeta::default(), query)
+    }
+
+    /// `get_update` retrieves the update details for the given update id.
+    /// The update id can be found in the `id` field of the `SearchResult` struct.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_id` - The update id to retrieve details for.
+    ///
+    /// # Example
+    ///
+    /// ```
     /// use msuc::prelude::*;
     /// use tokio_test;
     ///
     /// #[cfg(not(feature = "blocking"))]
     /// tokio_test::block_on(async {
     ///     let msuc_client = MsucClient::new().expect("Failed to create MSUC client");
-    ///    // MS08-067
-    ///     msuc_client.get_update("9397a21f-246c-453b-ac05-65bf4fc6b68b").await.expect("Failed to get update details");
+    ///     // MS08-067
+    ///     let update_id: UpdateId = "9397a21f-246c-453b-ac05-65bf4fc6b68b".parse().expect("Failed to parse update id");
+    ///     msuc_client.get_update(&update_id).await.expect("Failed to get update details");
     /// });
     /// ```
     ///
@@ -277,42 +1218,2941 @@ impl Client {
     /// {
     ///     let msuc_client = MsucClient::new().expect("Failed to create MSUC client");
     ///     // MS08-067
-    ///     msuc_client.get_update("9397a21f-246c-453b-ac05-65bf4fc6b68b").expect("Failed to get update details");
+    ///     let update_id: UpdateId = "9397a21f-246c-453b-ac05-65bf4fc6b68b".parse().expect("Failed to parse update id");
+    ///     msuc_client.get_update(&update_id).expect("Failed to get update details");
     /// }
     #[cfg(not(feature = "blocking"))]
-    pub async fn get_update(&self, update_id: &str) -> Result<Update, Error> {
-        let url = format!("{}{}", self.update_url, update_id);
-        let resp = self
-            .client
-            .get(url.as_str())
-            .send()
-            .await
-            .map_err(Error::Client)?;
-        resp.error_for_status_ref()?;
-        let html = resp.text().await.map_err(Error::Client)?;
-        parse_update_details(&html).map_err(|e| {
-            Error::Search(format!(
-                "Failed to parse update details for {}: {:?}",
-                update_id, e
-            ))
-        })
+    pub async fn get_update(&self, update_id: &UpdateId) -> Result<Update, Error> {
+        let html = self.get_update_html(update_id).await?;
+        parse_update_response(update_id, &html)
     }
 
     #[cfg(feature = "blocking")]
-    pub fn get_update(&self, update_id: &str) -> Result<Update, Error> {
-        let url = format!("{}{}", self.update_url, update_id);
-        let resp = self
-            .client
-            .get(url.as_str())
-            .send()
-            .map_err(Error::Client)?;
-        resp.error_for_status_ref()?;
-        let html = resp.text().map_err(Error::Client)?;
-        parse_update_details(&html).map_err(|e| {
-            Error::Search(format!(
-                "Failed to parse update details for {}: {:?}",
-                update_id, e
-            ))
-        })
+    pub fn get_update(&self, update_id: &UpdateId) -> Result<Update, Error> {
+        let html = self.get_update_html(update_id)?;
+        parse_update_response(update_id, &html)
+    }
+
+    /// `parse_update_from_bytes` parses an update details page from the raw bytes of a response,
+    /// such as one captured elsewhere or fetched through a proxy. The bytes are decoded using the
+    /// byte order mark or the charset the page declares, and as UTF-8, the charset the catalog
+    /// serves, if neither is present.
+    ///
+    /// # Parameters
+    ///
+    /// * `bytes` - The body of the update details page.
+    pub fn parse_update_from_bytes(&self, bytes: &[u8]) -> Result<Update, Error> {
+        parse_update_details(&decode_html(bytes, None))
+    }
+
+    /// `get_update_owned` retrieves the update details for the given update id like `get_update`,
+    /// but the returned future owns the client and the id, so it can be spawned as a `'static`
+    /// task, e.g. with `tokio::task::JoinSet::spawn`.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_id` - The update id to retrieve details for.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use msuc::prelude::*;
+    /// use std::sync::Arc;
+    /// use tokio_test;
+    ///
+    /// #[cfg(not(feature = "blocking"))]
+    /// tokio_test::block_on(async {
+    ///     let msuc_client = Arc::new(MsucClient::new().expect("Failed to create MSUC client"));
+    ///     let mut join_set = tokio::task::JoinSet::new();
+    ///     // MS08-067
+    ///     let update_id: UpdateId = "9397a21f-246c-453b-ac05-65bf4fc6b68b".parse().expect("Failed to parse update id");
+    ///     join_set.spawn(msuc_client.clone().get_update_owned(update_id));
+    ///     while let Some(update) = join_set.join_next().await {
+    ///         update.expect("Failed to join task").expect("Failed to get update details");
+    ///     }
+    /// });
+    /// ```
+    #[cfg(not(feature = "blocking"))]
+    pub async fn get_update_owned(self: Arc<Self>, update_id: UpdateId) -> Result<Update, Error> {
+        self.get_update(&update_id).await
+    }
+
+    /// `get_update_for` retrieves the update details for the given search result. The title of
+    /// the search result is kept in `search_title` if it differs from the title on the details
+    /// page.
+    ///
+    /// # Parameters
+    ///
+    /// * `result` - The search result to retrieve details for.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn get_update_for(&self, result: &SearchResult) -> Result<Update, Error> {
+        let update = self.get_update(&result.id).await?;
+        Ok(with_search_title(update, result))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn get_update_for(&self, result: &SearchResult) -> Result<Update, Error> {
+        let update = self.get_update(&result.id)?;
+        Ok(with_search_title(update, result))
+    }
+
+    /// `self_test` checks that the crate can still parse the catalog by retrieving a known update
+    /// that is not expected to change and comparing its key fields to their known values. It
+    /// returns an error listing the fields that did not match if the layout of the catalog has
+    /// changed. This makes a request to the catalog, so it is suited to a scheduled check rather
+    /// than to running before every use of the client.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn self_test(&self) -> Result<(), Error> {
+        check_self_test_update(&self.get_update(&SELF_TEST_UPDATE_ID.parse()?).await?)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn self_test(&self) -> Result<(), Error> {
+        check_self_test_update(&self.get_update(&SELF_TEST_UPDATE_ID.parse()?)?)
+    }
+
+    /// `get_download_dialog_raw` retrieves the download dialog for the given update id and returns
+    /// the body of the response as is. The dialog lists the files of the update and changes more
+    /// often than the details page, so it is not parsed; this lets callers extract the fields they
+    /// need themselves.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_id` - The update id to retrieve the download dialog for.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn get_download_dialog_raw(&self, update_id: &UpdateId) -> Result<String, Error> {
+        let builder = self.get_download_dialog_builder(update_id)?;
+        self.fetch_text(builder).await
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn get_download_dialog_raw(&self, update_id: &UpdateId) -> Result<String, Error> {
+        let builder = self.get_download_dialog_builder(update_id)?;
+        self.fetch_text(builder)
+    }
+
+    /// `get_update_for_superseded_by` retrieves the update details of an update that supersedes
+    /// another, to walk forward through the supersedence chain.
+    ///
+    /// # Parameters
+    ///
+    /// * `superseded_by` - The superseding update to retrieve details for.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn get_update_for_superseded_by(&self, superseded_by: &SupersededByUpdate) -> Result<Update, Error> {
+        self.get_update(&superseded_by.id).await
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn get_update_for_superseded_by(&self, superseded_by: &SupersededByUpdate) -> Result<Update, Error> {
+        self.get_update(&superseded_by.id)
+    }
+
+    /// `get_update_freshness` retrieves only the last modified date and size of the given update,
+    /// for checking whether a cached copy of the update is out of date without parsing the rest of
+    /// the update details.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_id` - The update id to retrieve the last modified date and size for.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn get_update_freshness(&self, update_id: &UpdateId) -> Result<(chrono::NaiveDate, u64), Error> {
+        let update = self.get_update_fields(update_id, UpdateFields::LAST_MODIFIED | UpdateFields::SIZE).await?;
+        freshness_from(update)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn get_update_freshness(&self, update_id: &UpdateId) -> Result<(chrono::NaiveDate, u64), Error> {
+        let update = self.get_update_fields(update_id, UpdateFields::LAST_MODIFIED | UpdateFields::SIZE)?;
+        freshness_from(update)
+    }
+
+    /// `get_update_fields` retrieves only the selected sections of the update details for the given
+    /// update id. Sections that were not selected are not parsed, which is cheaper when retrieving
+    /// many updates, and a section that fails to parse can't fail the call unless it was selected.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_id` - The update id to retrieve details for.
+    /// * `fields` - The sections of the update details to parse.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn get_update_fields(&self, update_id: &UpdateId, fields: UpdateFields) -> Result<PartialUpdate, Error> {
+        let html = self.get_update_html(update_id).await?;
+        parse_update_fields(&html, fields)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn get_update_fields(&self, update_id: &UpdateId, fields: UpdateFields) -> Result<PartialUpdate, Error> {
+        let html = self.get_update_html(update_id)?;
+        parse_update_fields(&html, fields)
+    }
+
+    /// `get_update_with_stats` retrieves the update details for the given update id like
+    /// `get_update`, along with how many attempts the request took and how long was spent waiting
+    /// between them. A rising number of attempts is an early sign the catalog is throttling
+    /// requests. Retries are only made when enabled with `ClientBuilder::max_retries`.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_id` - The update id to retrieve details for.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn get_update_with_stats(&self, update_id: &UpdateId) -> Result<(Update, RequestStats), Error> {
+        let (html, stats) = self.get_update_html_with_stats(update_id).await?;
+        Ok((parse_update_response(update_id, &html)?, stats))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn get_update_with_stats(&self, update_id: &UpdateId) -> Result<(Update, RequestStats), Error> {
+        let (html, stats) = self.get_update_html_with_stats(update_id)?;
+        Ok((parse_update_response(update_id, &html)?, stats))
+    }
+
+    /// `get_update_timed` retrieves the update details for the given update id like `get_update`,
+    /// along with how long requesting and parsing the page took. This helps tell whether the
+    /// catalog or parsing is the bottleneck when retrieving many updates.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_id` - The update id to retrieve details for.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn get_update_timed(&self, update_id: &UpdateId) -> Result<(Update, Timing), Error> {
+        let start = Instant::now();
+        let html = self.get_update_html(update_id).await?;
+        let request = start.elapsed();
+        let update = parse_update_response(update_id, &html)?;
+        let total = start.elapsed();
+        Ok((update, Timing { request, parse: total - request, total }))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn get_update_timed(&self, update_id: &UpdateId) -> Result<(Update, Timing), Error> {
+        let start = Instant::now();
+        let html = self.get_update_html(update_id)?;
+        let request = start.elapsed();
+        let update = parse_update_response(update_id, &html)?;
+        let total = start.elapsed();
+        Ok((update, Timing { request, parse: total - request, total }))
+    }
+
+    /// `summarize` retrieves a lightweight summary of the update for the given update id. Only the
+    /// title, id, kb, classification, last modified date, and size are parsed from the details
+    /// page, which makes it a cheaper and more tolerant lookup than `get_update` when only the
+    /// identity of the update is needed.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_id` - The update id to retrieve the summary for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use msuc::prelude::*;
+    /// use tokio_test;
+    ///
+    /// #[cfg(not(feature = "blocking"))]
+    /// tokio_test::block_on(async {
+    ///     let msuc_client = MsucClient::new().expect("Failed to create MSUC client");
+    ///     // MS08-067
+    ///     let update_id: UpdateId = "9397a21f-246c-453b-ac05-65bf4fc6b68b".parse().expect("Failed to parse update id");
+    ///     msuc_client.summarize(&update_id).await.expect("Failed to get update summary");
+    /// });
+    /// ```
+    ///
+    /// ```
+    /// use msuc::prelude::*;
+    ///
+    /// #[cfg(feature = "blocking")]
+    /// {
+    ///     let msuc_client = MsucClient::new().expect("Failed to create MSUC client");
+    ///     // MS08-067
+    ///     let update_id: UpdateId = "9397a21f-246c-453b-ac05-65bf4fc6b68b".parse().expect("Failed to parse update id");
+    ///     msuc_client.summarize(&update_id).expect("Failed to get update summary");
+    /// }
+    /// ```
+    #[cfg(not(feature = "blocking"))]
+    pub async fn summarize(&self, update_id: &UpdateId) -> Result<UpdateSummary, Error> {
+        let html = self.get_update_html(update_id).await?;
+        parse_update_summary(&html).map_err(|e| match e {
+            Error::Msuc(_, _) => e,
+            _ => Error::Search(format!(
+                "Failed to parse update summary for {}: {:?}",
+                update_id, e
+            )),
+        })
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn summarize(&self, update_id: &UpdateId) -> Result<UpdateSummary, Error> {
+        let html = self.get_update_html(update_id)?;
+        parse_update_summary(&html).map_err(|e| match e {
+            Error::Msuc(_, _) => e,
+            _ => Error::Search(format!(
+                "Failed to parse update summary for {}: {:?}",
+                update_id, e
+            )),
+        })
+    }
+
+    /// `get_update_document` retrieves the details page for the given update id and returns the
+    /// parsed HTML document, so fields that are not part of `Update` can be extracted with custom
+    /// selectors. The document type comes from the `scraper` crate, so a `scraper` upgrade in this
+    /// crate can be a breaking change for callers of this method.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_id` - The update id to retrieve the details page for.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn get_update_document(&self, update_id: &UpdateId) -> Result<scraper::Html, Error> {
+        let html = self.get_update_html(update_id).await?;
+        Ok(scraper::Html::parse_document(&html))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn get_update_document(&self, update_id: &UpdateId) -> Result<scraper::Html, Error> {
+        let html = self.get_update_html(update_id)?;
+        Ok(scraper::Html::parse_document(&html))
+    }
+
+    /// `search_document` retrieves the first page of search results for the query and returns the
+    /// parsed HTML document, so data that is not part of `SearchResult` can be extracted with
+    /// custom selectors. The same `scraper` version caveat as `get_update_document` applies.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to use.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn search_document(&self, query: &str) -> Result<scraper::Html, Error> {
+        let builder = self.get_search_builder(query, &SearchPageMeta::default())?;
+        let html = self.fetch_text(builder).await?;
+        Ok(scraper::Html::parse_document(&html))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn search_document(&self, query: &str) -> Result<scraper::Html, Error> {
+        let builder = self.get_search_builder(query, &SearchPageMeta::default())?;
+        let html = self.fetch_text(builder)?;
+        Ok(scraper::Html::parse_document(&html))
+    }
+
+    /// `resolve_download_redirect` follows the redirects for a download url, such as the
+    /// `download.windowsupdate.com` urls for an update's files, and returns the final url along
+    /// with the `Content-Length` if the server reports one. A `HEAD` request is used so the file
+    /// itself is not downloaded.
+    ///
+    /// # Parameters
+    ///
+    /// * `url` - The download url to resolve.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn resolve_download_redirect(&self, url: &Url) -> Result<ResolvedDownload, Error> {
+        let _permit = match &self.limiter {
+            Some(l) => Some(l.acquire().await?),
+            None => None,
+        };
+        let resp = self.send(self.client.head(url.as_str())).await?;
+        Ok(ResolvedDownload {
+            url: resp.url().clone(),
+            content_length: parse_content_length(resp.headers()),
+        })
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn resolve_download_redirect(&self, url: &Url) -> Result<ResolvedDownload, Error> {
+        let _permit = match &self.limiter {
+            Some(l) => Some(l.acquire()?),
+            None => None,
+        };
+        let resp = self.send(self.client.head(url.as_str()))?;
+        Ok(ResolvedDownload {
+            url: resp.url().clone(),
+            content_length: parse_content_length(resp.headers()),
+        })
+    }
+
+    /// `same_lineage` returns true if the two updates are part of the same supersedence lineage,
+    /// meaning one supersedes the other or both are superseded by a common update. The
+    /// superseded by links of each update are followed up to `max_depth` levels and each update is
+    /// only retrieved once.
+    ///
+    /// # Parameters
+    ///
+    /// * `a` - The update id of the first update.
+    /// * `b` - The update id of the second update.
+    /// * `max_depth` - The maximum number of superseded by links to follow from each update.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn same_lineage(&self, a: &UpdateId, b: &UpdateId, max_depth: usize) -> Result<bool, Error> {
+        if a == b {
+            return Ok(true);
+        }
+        let mut cache = HashMap::new();
+        let a_lineage = self.crawl_superseded_by(a, max_depth, &mut cache).await?;
+        if a_lineage.contains(b) {
+            return Ok(true);
+        }
+        let b_lineage = self.crawl_superseded_by(b, max_depth, &mut cache).await?;
+        Ok(!a_lineage.is_disjoint(&b_lineage))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn same_lineage(&self, a: &UpdateId, b: &UpdateId, max_depth: usize) -> Result<bool, Error> {
+        if a == b {
+            return Ok(true);
+        }
+        let mut cache = HashMap::new();
+        let a_lineage = self.crawl_superseded_by(a, max_depth, &mut cache)?;
+        if a_lineage.contains(b) {
+            return Ok(true);
+        }
+        let b_lineage = self.crawl_superseded_by(b, max_depth, &mut cache)?;
+        Ok(!a_lineage.is_disjoint(&b_lineage))
+    }
+
+    /// `supersedence_timeline` returns the update and the updates that supersede it, following
+    /// the superseded by links up to `max_depth` levels, sorted by last modified date from oldest
+    /// to newest. The catalog only links the updates that supersede an update, not the updates it
+    /// supersedes, so the timeline starts at the given update. Each update is only retrieved
+    /// once, so a cycle in the links does not repeat updates.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_id` - The update id to start the timeline from.
+    /// * `max_depth` - The maximum number of superseded by links to follow.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn supersedence_timeline(&self, update_id: &UpdateId, max_depth: usize) -> Result<Vec<Update>, Error> {
+        let mut visited = HashSet::from([update_id.clone()]);
+        let mut frontier = vec![update_id.clone()];
+        let mut updates = vec![];
+        for depth in 0..=max_depth {
+            let mut next = vec![];
+            for id in frontier {
+                let update = self.get_update(&id).await?;
+                if depth < max_depth {
+                    let superseding_ids = update.superseded_by.iter().map(|u| u.id.clone());
+                    next.extend(superseding_ids.filter(|id| visited.insert(id.clone())));
+                }
+                updates.push(update);
+            }
+            frontier = next;
+        }
+        updates.sort_by_key(|u| u.last_modified);
+        Ok(updates)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn supersedence_timeline(&self, update_id: &UpdateId, max_depth: usize) -> Result<Vec<Update>, Error> {
+        let mut visited = HashSet::from([update_id.clone()]);
+        let mut frontier = vec![update_id.clone()];
+        let mut updates = vec![];
+        for depth in 0..=max_depth {
+            let mut next = vec![];
+            for id in frontier {
+                let update = self.get_update(&id)?;
+                if depth < max_depth {
+                    let superseding_ids = update.superseded_by.iter().map(|u| u.id.clone());
+                    next.extend(superseding_ids.filter(|id| visited.insert(id.clone())));
+                }
+                updates.push(update);
+            }
+            frontier = next;
+        }
+        updates.sort_by_key(|u| u.last_modified);
+        Ok(updates)
+    }
+
+    /// `crawl_superseded_by` returns the ids of the update and every update that supersedes it,
+    /// following the superseded by links up to `max_depth` levels. The superseded by ids of each
+    /// retrieved update are stored in `cache` so they can be reused across crawls.
+    #[cfg(not(feature = "blocking"))]
+    async fn crawl_superseded_by(
+        &self,
+        update_id: &UpdateId,
+        max_depth: usize,
+        cache: &mut HashMap<UpdateId, Vec<UpdateId>>,
+    ) -> Result<HashSet<UpdateId>, Error> {
+        let mut visited = HashSet::from([update_id.clone()]);
+        let mut frontier = vec![update_id.clone()];
+        for _ in 0..max_depth {
+            let mut next = vec![];
+            for id in frontier {
+                if !cache.contains_key(&id) {
+                    let update = self.get_update(&id).await?;
+                    cache.insert(id.clone(), update.superseded_by.into_iter().map(|u| u.id).collect());
+                }
+                for superseding_id in &cache[&id] {
+                    if visited.insert(superseding_id.clone()) {
+                        next.push(superseding_id.clone());
+                    }
+                }
+            }
+            frontier = next;
+        }
+        Ok(visited)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn crawl_superseded_by(
+        &self,
+        update_id: &UpdateId,
+        max_depth: usize,
+        cache: &mut HashMap<UpdateId, Vec<UpdateId>>,
+    ) -> Result<HashSet<UpdateId>, Error> {
+        let mut visited = HashSet::from([update_id.clone()]);
+        let mut frontier = vec![update_id.clone()];
+        for _ in 0..max_depth {
+            let mut next = vec![];
+            for id in frontier {
+                if !cache.contains_key(&id) {
+                    let update = self.get_update(&id)?;
+                    cache.insert(id.clone(), update.superseded_by.into_iter().map(|u| u.id).collect());
+                }
+                for superseding_id in &cache[&id] {
+                    if visited.insert(superseding_id.clone()) {
+                        next.push(superseding_id.clone());
+                    }
+                }
+            }
+            frontier = next;
+        }
+        Ok(visited)
+    }
+
+    /// `search_all` retrieves the results of a search as a single `Vec`, deduplicated by update id
+    /// since the catalog occasionally repeats a row across pages. Pages are retrieved until there
+    /// are no more or `max_results` results have been collected. An error is returned if the
+    /// catalog reports the search has more than the 1000 results it will return, as the results
+    /// would be incomplete; use `search_all_capped` to accept the capped results instead.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to use.
+    /// * `max_results` - The maximum number of results to return.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn search_all(&self, query: &str, max_results: usize) -> Result<Vec<SearchResult>, Error> {
+        match self.collect_results(query, max_results, false).await? {
+            (_, true) => Err(too_many_results_error(query)),
+            (results, false) => Ok(results),
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn search_all(&self, query: &str, max_results: usize) -> Result<Vec<SearchResult>, Error> {
+        match self.collect_results(query, max_results, false)? {
+            (_, true) => Err(too_many_results_error(query)),
+            (results, false) => Ok(results),
+        }
+    }
+
+    /// `search_since` retrieves the results of a search last modified on or after `since`, for
+    /// example to sync the updates modified since the last run. It relies on the catalog
+    /// returning results newest first, and stops requesting pages once a page that is in that
+    /// order reaches a result older than `since`. A page that is not in that order is taken as a
+    /// sign the order is not guaranteed for the query, and the remaining pages are retrieved and
+    /// filtered instead. Results are deduplicated by update id and returned in the order the
+    /// catalog returned them. Only the 1000 results the catalog returns for a search are
+    /// considered.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to use.
+    /// * `since` - The earliest last modified date of the results to return.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn search_since(&self, query: &str, since: chrono::NaiveDate) -> Result<Vec<SearchResult>, Error> {
+        let mut stream = self.search(query)?;
+        let mut results = vec![];
+        let mut seen = HashSet::new();
+        let mut ordered = true;
+        while let Some(page) = stream.next().await? {
+            if collect_since(&mut results, &mut seen, &mut ordered, page, since) {
+                break;
+            }
+        }
+        Ok(results)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn search_since(&self, query: &str, since: chrono::NaiveDate) -> Result<Vec<SearchResult>, Error> {
+        let mut stream = self.search(query)?;
+        let mut results = vec![];
+        let mut seen = HashSet::new();
+        let mut ordered = true;
+        while let Some(page) = stream.next()? {
+            if collect_since(&mut results, &mut seen, &mut ordered, page, since) {
+                break;
+            }
+        }
+        Ok(results)
+    }
+
+    /// `search_union` runs each of the queries like `search_all` and returns the union of their
+    /// results, deduplicated by update id. Results are ordered by the first query that returned
+    /// them, then by the order the catalog returned them in. Up to four searches are run at the
+    /// same time. An error is returned if any of the searches fail.
+    ///
+    /// # Parameters
+    ///
+    /// * `queries` - The search queries to use.
+    /// * `max_per_query` - The maximum number of results to collect for each query.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn search_union(&self, queries: &[&str], max_per_query: usize) -> Result<Vec<SearchResult>, Error> {
+        let pages = futures::stream::iter(queries)
+            .map(|q| self.search_all(q, max_per_query))
+            .buffered(BATCH_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(union_results(pages))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn search_union(&self, queries: &[&str], max_per_query: usize) -> Result<Vec<SearchResult>, Error> {
+        let pages = queries
+            .iter()
+            .map(|q| self.search_all(q, max_per_query))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(union_results(pages))
+    }
+
+    /// `search_all_capped` retrieves the results of a search as a single `Vec` like `search_all`,
+    /// but returns the results even if the catalog reports the search has more than the 1000
+    /// results it will return. The returned flag is true in that case, meaning the results are
+    /// only the part of the search the catalog was willing to return.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to use.
+    /// * `max_results` - The maximum number of results to return.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn search_all_capped(&self, query: &str, max_results: usize) -> Result<(Vec<SearchResult>, bool), Error> {
+        self.collect_results(query, max_results, true).await
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn search_all_capped(&self, query: &str, max_results: usize) -> Result<(Vec<SearchResult>, bool), Error> {
+        self.collect_results(query, max_results, true)
+    }
+
+    // collect_results collects up to max_results unique results of a search along with whether
+    // the catalog capped the search. Unless allow_capped is set, collecting stops at the first
+    // page of a capped search.
+    #[cfg(not(feature = "blocking"))]
+    async fn collect_results(
+        &self,
+        query: &str,
+        max_results: usize,
+        allow_capped: bool,
+    ) -> Result<(Vec<SearchResult>, bool), Error> {
+        let mut stream = self.search(query)?;
+        let mut results = vec![];
+        let mut seen = HashSet::new();
+        while results.len() < max_results {
+            let page = match stream.next().await? {
+                Some(p) => p,
+                None => break,
+            };
+            if stream.too_many_results() && !allow_capped {
+                break;
+            }
+            results.extend(page.into_iter().filter(|r| seen.insert(r.id.clone())));
+        }
+        results.truncate(max_results);
+        Ok((results, stream.too_many_results()))
+    }
+
+    #[cfg(feature = "blocking")]
+    fn collect_results(
+        &self,
+        query: &str,
+        max_results: usize,
+        allow_capped: bool,
+    ) -> Result<(Vec<SearchResult>, bool), Error> {
+        let mut stream = self.search(query)?;
+        let mut results = vec![];
+        let mut seen = HashSet::new();
+        while results.len() < max_results {
+            let page = match stream.next()? {
+                Some(p) => p,
+                None => break,
+            };
+            if stream.too_many_results() && !allow_capped {
+                break;
+            }
+            results.extend(page.into_iter().filter(|r| seen.insert(r.id.clone())));
+        }
+        results.truncate(max_results);
+        Ok((results, stream.too_many_results()))
+    }
+
+    /// `resolve_kbs` searches the catalog for each KB number and returns the search results that
+    /// match it, in the same order as `kbs`. A failure to search for one KB number is returned
+    /// for that KB number without stopping the rest of the batch. Up to four searches are run at
+    /// the same time.
+    ///
+    /// # Parameters
+    ///
+    /// * `kbs` - The KB numbers to resolve, with or without the `KB` prefix.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn resolve_kbs(&self, kbs: &[&str]) -> Vec<(String, Result<Vec<SearchResult>, Error>)> {
+        futures::stream::iter(kbs)
+            .map(|kb| async move { (kb.to_string(), self.search_kb(kb).await) })
+            .buffered(BATCH_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn resolve_kbs(&self, kbs: &[&str]) -> Vec<(String, Result<Vec<SearchResult>, Error>)> {
+        kbs.iter()
+            .map(|kb| (kb.to_string(), self.search_kb(kb)))
+            .collect()
+    }
+
+    /// `search_current_only` searches the catalog and returns the details of the results that
+    /// have not been superseded. Supersedence is only listed on the update details page, so this
+    /// makes one details request per result checked on top of the search requests, which is
+    /// expensive for broad queries. At most `max_checks` results are checked, in the order the
+    /// catalog returns them, and up to four details requests are run at the same time. Results
+    /// past the cap are not considered, so a cap that is too low can miss current updates.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to use.
+    /// * `max_checks` - The maximum number of results to fetch the details for.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn search_current_only(&self, query: &str, max_checks: usize) -> Result<Vec<Update>, Error> {
+        let ids = self.search_ids(query, max_checks).await?;
+        let updates = futures::stream::iter(&ids)
+            .map(|id| self.get_update(id))
+            .buffered(BATCH_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(updates.into_iter().filter(|u| u.superseded_by.is_empty()).collect())
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn search_current_only(&self, query: &str, max_checks: usize) -> Result<Vec<Update>, Error> {
+        let mut updates = vec![];
+        for id in self.search_ids(query, max_checks)? {
+            let update = self.get_update(&id)?;
+            if update.superseded_by.is_empty() {
+                updates.push(update);
+            }
+        }
+        Ok(updates)
+    }
+
+    /// `estimate_total_size` returns the total size in bytes of the given updates, as reported by
+    /// the catalog, for example to provision storage before mirroring them. An update id listed
+    /// more than once is only counted once. The summary of each update is retrieved, with up to
+    /// four requests run at the same time.
+    ///
+    /// # Parameters
+    ///
+    /// * `update_ids` - The ids of the updates to total.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn estimate_total_size(&self, update_ids: &[UpdateId]) -> Result<u64, Error> {
+        let mut seen = HashSet::new();
+        let sizes = futures::stream::iter(update_ids.iter().filter(|id| seen.insert(*id)))
+            .map(|id| async move { self.summarize(id).await.map(|s| s.size) })
+            .buffered(BATCH_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sizes.iter().sum())
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn estimate_total_size(&self, update_ids: &[UpdateId]) -> Result<u64, Error> {
+        let mut seen = HashSet::new();
+        let mut total = 0;
+        for id in update_ids.iter().filter(|id| seen.insert(*id)) {
+            total += self.summarize(id)?.size;
+        }
+        Ok(total)
+    }
+
+    /// `search_with_details` searches the catalog and retrieves the update details for each
+    /// result, yielding the updates as their details are retrieved. This makes one details
+    /// request per result on top of the search requests. Up to `concurrency` details requests
+    /// are run at the same time, so updates are not necessarily yielded in the order of the
+    /// search results. The stream ends after the first search error.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to use.
+    /// * `concurrency` - The maximum number of details requests to run at the same time.
+    #[cfg(not(feature = "blocking"))]
+    pub fn search_with_details<'a>(
+        &'a self,
+        query: &str,
+        concurrency: usize,
+    ) -> impl futures::Stream<Item = Result<Update, Error>> + 'a {
+        self.search_enriched(query, concurrency, 0)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn search_with_details<'a>(
+        &'a self,
+        query: &str,
+        concurrency: usize,
+    ) -> impl Iterator<Item = Result<Update, Error>> + 'a {
+        self.search_enriched(query, concurrency, 0)
+    }
+
+    /// `search_enriched` searches the catalog and retrieves the update details for each result
+    /// like `search_with_details`, retrying each search page and details request that fails
+    /// before a response is received or with an unsuccessful status up to `retries` times. The
+    /// client waits for `ClientBuilder::retry_backoff` before each retry. Catalog errors and
+    /// pages that fail to parse are not retried, and throttled responses are retried separately
+    /// according to `ClientBuilder::max_retries`.
+    ///
+    /// The stream is lazy: the next page of search results is only requested once the details
+    /// of the previous page have been requested and the stream is polled for more, and no more
+    /// than `concurrency` details requests are in flight at a time, so a slow consumer slows the
+    /// requests down rather than buffering updates. Updates are yielded in the order their
+    /// details are retrieved, not the order of the search results. A details request that still
+    /// fails after its retries is yielded as an error without ending the stream, while the
+    /// stream ends after a search page that still fails.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to use.
+    /// * `concurrency` - The maximum number of details requests to run at the same time.
+    /// * `retries` - The number of times to retry each failed request.
+    #[cfg(not(feature = "blocking"))]
+    pub fn search_enriched<'a>(
+        &'a self,
+        query: &str,
+        concurrency: usize,
+        retries: u32,
+    ) -> impl futures::Stream<Item = Result<Update, Error>> + 'a {
+        futures::stream::unfold(Some(self.search(query)), move |state| async move {
+            let mut stream = match state? {
+                Ok(s) => s,
+                Err(e) => return Some((vec![Err(e)], None)),
+            };
+            let mut attempts = 0;
+            loop {
+                match stream.next().await {
+                    Ok(Some(page)) => return Some((page.into_iter().map(Ok).collect::<Vec<_>>(), Some(Ok(stream)))),
+                    Ok(None) => return None,
+                    Err(e) if attempts < retries && is_retryable(&e) => {
+                        attempts += 1;
+                        tokio::time::sleep(self.config.retry_backoff).await;
+                    }
+                    Err(e) => return Some((vec![Err(e)], None)),
+                }
+            }
+        })
+        .flat_map(futures::stream::iter)
+        .map(move |r| async move {
+            match r {
+                Ok(r) => self.get_update_for_with_retries(&r, retries).await,
+                Err(e) => Err(e),
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn search_enriched<'a>(
+        &'a self,
+        query: &str,
+        _concurrency: usize,
+        retries: u32,
+    ) -> impl Iterator<Item = Result<Update, Error>> + 'a {
+        // details are requested one at a time in blocking mode, like the other batch methods
+        let mut stream = Some(self.search(query));
+        let mut pending: VecDeque<SearchResult> = VecDeque::new();
+        let mut attempts = 0;
+        std::iter::from_fn(move || loop {
+            if let Some(r) = pending.pop_front() {
+                return Some(self.get_update_for_with_retries(&r, retries));
+            }
+            match stream.take()? {
+                Ok(mut s) => match s.next() {
+                    Ok(Some(page)) => {
+                        attempts = 0;
+                        pending.extend(page);
+                        stream = Some(Ok(s));
+                    }
+                    Ok(None) => return None,
+                    Err(e) if attempts < retries && is_retryable(&e) => {
+                        attempts += 1;
+                        std::thread::sleep(self.config.retry_backoff);
+                        stream = Some(Ok(s));
+                    }
+                    Err(e) => return Some(Err(e)),
+                },
+                Err(e) => return Some(Err(e)),
+            }
+        })
+    }
+
+    /// `search_into_channel` searches the catalog and sends each result to `tx`, for a separate
+    /// task to receive and process. Sending waits while the channel is full, so pages are only
+    /// requested as fast as the receiver keeps up. Results are sent in the order the catalog
+    /// returns them. A search error is sent to the channel and ends the search, and the search
+    /// also ends early if the receiver is dropped.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to use.
+    /// * `tx` - The channel to send the results to.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn search_into_channel(&self, query: &str, tx: tokio::sync::mpsc::Sender<Result<SearchResult, Error>>) {
+        let mut stream = match self.search(query) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+        };
+        loop {
+            match stream.next().await {
+                Ok(Some(page)) => {
+                    for r in page {
+                        if tx.send(Ok(r)).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+                Ok(None) => return,
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// `query_too_broad` returns true if the search for `query` matches more results than the
+    /// catalog will return. Only the first page is fetched, so this can be checked before
+    /// committing to crawling every page of the search.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to check.
+    #[cfg(not(feature = "blocking"))]
+    pub async fn query_too_broad(&self, query: &str) -> Result<bool, Error> {
+        let mut stream = self.search(query)?;
+        stream.next().await?;
+        Ok(stream.too_many_results())
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn query_too_broad(&self, query: &str) -> Result<bool, Error> {
+        let mut stream = self.search(query)?;
+        stream.next()?;
+        Ok(stream.too_many_results())
+    }
+
+    // get_update_for_with_retries retries get_update_for when the request fails.
+    #[cfg(not(feature = "blocking"))]
+    async fn get_update_for_with_retries(&self, result: &SearchResult, retries: u32) -> Result<Update, Error> {
+        let mut attempts = 0;
+        loop {
+            match self.get_update_for(result).await {
+                Err(e) if attempts < retries && is_retryable(&e) => {
+                    attempts += 1;
+                    tokio::time::sleep(self.config.retry_backoff).await;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    fn get_update_for_with_retries(&self, result: &SearchResult, retries: u32) -> Result<Update, Error> {
+        let mut attempts = 0;
+        loop {
+            match self.get_update_for(result) {
+                Err(e) if attempts < retries && is_retryable(&e) => {
+                    attempts += 1;
+                    std::thread::sleep(self.config.retry_backoff);
+                }
+                res => return res,
+            }
+        }
+    }
+
+    // search_ids returns the unique update ids of up to max_results results of a search.
+    #[cfg(not(feature = "blocking"))]
+    async fn search_ids(&self, query: &str, max_results: usize) -> Result<Vec<UpdateId>, Error> {
+        let mut stream = self.search(query)?;
+        let mut ids = vec![];
+        let mut seen = HashSet::new();
+        while ids.len() < max_results {
+            match stream.next().await? {
+                Some(page) => ids.extend(page.into_iter().map(|r| r.id).filter(|id| seen.insert(id.clone()))),
+                None => break,
+            }
+        }
+        ids.truncate(max_results);
+        Ok(ids)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn search_ids(&self, query: &str, max_results: usize) -> Result<Vec<UpdateId>, Error> {
+        let mut stream = self.search(query)?;
+        let mut ids = vec![];
+        let mut seen = HashSet::new();
+        while ids.len() < max_results {
+            match stream.next()? {
+                Some(page) => ids.extend(page.into_iter().map(|r| r.id).filter(|id| seen.insert(id.clone()))),
+                None => break,
+            }
+        }
+        ids.truncate(max_results);
+        Ok(ids)
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    async fn search_kb(&self, kb: &str) -> Result<Vec<SearchResult>, Error> {
+        let mut stream = self.search(kb)?;
+        let mut results = vec![];
+        while let Some(page) = stream.next().await? {
+            results.extend(page.into_iter().filter(|r| r.kb.as_deref().is_some_and(|k| is_same_kb(k, kb))));
+        }
+        Ok(results)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn search_kb(&self, kb: &str) -> Result<Vec<SearchResult>, Error> {
+        let mut stream = self.search(kb)?;
+        let mut results = vec![];
+        while let Some(page) = stream.next()? {
+            results.extend(page.into_iter().filter(|r| r.kb.as_deref().is_some_and(|k| is_same_kb(k, kb))));
+        }
+        Ok(results)
+    }
+}
+
+// parse_update_response parses the update details page, passing the catalog error through so
+// callers can tell it apart from parse failures.
+fn parse_update_response(update_id: &str, html: &str) -> Result<Update, Error> {
+    parse_update_details(html).map_err(|e| match e {
+        Error::Msuc(_, _) => e,
+        _ => Error::Search(format!(
+            "Failed to parse update details for {}: {:?}",
+            update_id, e
+        )),
+    })
+}
+
+// add_request_stats combines the stats of the requests made for a single page.
+fn add_request_stats(a: RequestStats, b: RequestStats) -> RequestStats {
+    RequestStats {
+        attempts: a.attempts + b.attempts,
+        total_wait: a.total_wait + b.total_wait,
+    }
+}
+
+// check_blank_update_page returns an error if the details page is still blank after a retry.
+fn check_blank_update_page(update_id: &str, html: &str) -> Result<(), Error> {
+    match is_blank_update_page(html) {
+        true => Err(Error::Parsing(format!(
+            "The catalog returned a blank details page for {} twice",
+            update_id
+        ))),
+        false => Ok(()),
+    }
+}
+
+// check_self_test_update compares the fields of the self test update to their known values.
+fn check_self_test_update(update: &Update) -> Result<(), Error> {
+    let expected = [
+        ("id", SELF_TEST_UPDATE_ID, update.id.as_str()),
+        ("title", "Security Update for Windows XP x64 Edition (KB958644)", update.title.as_str()),
+        ("kb", "958644", update.kb.as_str()),
+        ("msrc_number", "MS08-067", update.msrc_number.as_deref().unwrap_or_default()),
+    ];
+    let mismatches = expected
+        .iter()
+        .filter(|(_, want, got)| want != got)
+        .map(|(field, want, got)| format!("{} was '{}', expected '{}'", field, got, want))
+        .collect::<Vec<String>>();
+    match mismatches.is_empty() {
+        true => Ok(()),
+        false => Err(Error::Parsing(format!(
+            "Catalog layout no longer matches the parser: {}",
+            mismatches.join("; ")
+        ))),
+    }
+}
+
+// collect_since adds the results of a page modified on or after since, and returns true once the
+// remaining pages can be skipped because the results so far are newest first and the page reached
+// an older result.
+fn collect_since(
+    results: &mut Vec<SearchResult>,
+    seen: &mut HashSet<UpdateId>,
+    ordered: &mut bool,
+    page: Vec<SearchResult>,
+    since: chrono::NaiveDate,
+) -> bool {
+    let dates = results
+        .last()
+        .into_iter()
+        .chain(&page)
+        .map(|r| r.last_modified)
+        .collect::<Vec<_>>();
+    *ordered = *ordered && dates.windows(2).all(|w| w[0] >= w[1]);
+    let reached_older = page.iter().any(|r| r.last_modified < since);
+    results.extend(page.into_iter().filter(|r| r.last_modified >= since && seen.insert(r.id.clone())));
+    *ordered && reached_older
+}
+
+// union_results merges the results of several searches, keeping the first result for each id.
+fn union_results(pages: Vec<Vec<SearchResult>>) -> Vec<SearchResult> {
+    let mut seen = HashSet::new();
+    pages
+        .into_iter()
+        .flatten()
+        .filter(|r| seen.insert(r.id.clone()))
+        .collect()
+}
+
+// freshness_from takes the last modified date and size out of a partial update that selected them.
+fn freshness_from(update: PartialUpdate) -> Result<(chrono::NaiveDate, u64), Error> {
+    match (update.last_modified, update.size) {
+        (Some(last_modified), Some(size)) => Ok((last_modified, size)),
+        _ => Err(Error::Internal("Last modified date and size were not parsed".to_string())),
+    }
+}
+
+// with_search_title sets the search title of the update if it differs from the details title.
+fn with_search_title(mut update: Update, result: &SearchResult) -> Update {
+    if update.title != result.title {
+        update.search_title = Some(result.title.clone());
+    }
+    update
+}
+
+fn too_many_results_error(query: &str) -> Error {
+    Error::Search(format!(
+        "Search for {} returned more than the 1000 results the catalog will return",
+        query
+    ))
+}
+
+// read_body reads the body of the response, failing once it exceeds max_bytes.
+#[cfg(not(feature = "blocking"))]
+async fn read_body(mut resp: Response, max_bytes: usize) -> Result<Vec<u8>, Error> {
+    if resp.content_length().is_some_and(|l| l > max_bytes as u64) {
+        return Err(Error::ResponseTooLarge(max_bytes));
+    }
+    let mut body = vec![];
+    while let Some(chunk) = resp.chunk().await.map_err(Error::Client)? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(Error::ResponseTooLarge(max_bytes));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+#[cfg(feature = "blocking")]
+fn read_body(resp: Response, max_bytes: usize) -> Result<Vec<u8>, Error> {
+    use std::io::Read;
+    if resp.content_length().is_some_and(|l| l > max_bytes as u64) {
+        return Err(Error::ResponseTooLarge(max_bytes));
+    }
+    let mut body = vec![];
+    // one byte past the limit is read to tell a body at the limit from a larger one
+    resp.take(max_bytes as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| Error::Internal(format!("Failed to read response: {}", e)))?;
+    match body.len() > max_bytes {
+        true => Err(Error::ResponseTooLarge(max_bytes)),
+        false => Ok(body),
+    }
+}
+
+// get_content_type returns the Content-Type header of the response, if it has one.
+fn get_content_type(resp: &Response) -> Option<String> {
+    resp.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+// is_retryable returns true for errors from requests that failed or returned an unsuccessful
+// status, which may succeed if made again.
+fn is_retryable(e: &Error) -> bool {
+    matches!(e, Error::Client(_))
+}
+
+// is_same_kb compares KB numbers, ignoring the optional `KB` prefix.
+fn is_same_kb(a: &str, b: &str) -> bool {
+    compare_kb(a, b) == std::cmp::Ordering::Equal
+}
+
+// get_request_key identifies a request in a cassette by its method and url.
+#[cfg(feature = "cassette")]
+fn get_request_key(builder: &RequestBuilder) -> Result<String, Error> {
+    let request = builder
+        .try_clone()
+        .ok_or(Error::Internal("Failed to clone request".to_string()))?
+        .build()
+        .map_err(Error::Client)?;
+    Ok(format!("{} {}", request.method(), request.url()))
+}
+
+// is_throttled returns true for the responses the catalog uses to throttle clients.
+fn is_throttled(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+}
+
+// get_retry_delay returns the delay advertised by the Retry-After header, which can either be a
+// number of seconds or an HTTP date, falling back to the given backoff. The delay is limited to
+// max_delay so a server can't stall the client indefinitely.
+fn get_retry_delay(headers: &reqwest::header::HeaderMap, backoff: Duration, max_delay: Duration) -> Duration {
+    let value = match headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(v) => v.trim(),
+        None => return backoff.min(max_delay),
+    };
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Duration::from_secs(seconds).min(max_delay);
+    }
+    match chrono::DateTime::parse_from_rfc2822(value) {
+        Ok(date) => (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            // the date has already passed
+            .unwrap_or(Duration::ZERO)
+            .min(max_delay),
+        Err(_) => backoff.min(max_delay),
+    }
+}
+
+// get_response_info describes the response before its body is read, so the body length is taken
+// from the Content-Length header.
+fn get_response_info(method: String, resp: &Response) -> ResponseInfo {
+    ResponseInfo {
+        method,
+        url: resp.url().clone(),
+        status: resp.status().as_u16(),
+        body_len: parse_content_length(resp.headers()),
+    }
+}
+
+// parse_content_length reads the Content-Length header directly since the body of a HEAD
+// response is always empty.
+fn parse_content_length(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    headers
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{channel, Receiver};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use crate::parser::parse_search_results_only;
+
+    // serve accepts a connection for each of the given responses in order and writes the response
+    // once the request headers have been read. It returns the base url of the server and a
+    // receiver for the lowercased request headers.
+    fn serve(responses: Vec<Vec<u8>>) -> (String, Receiver<String>) {
+        let (tx, rx) = channel();
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind test server");
+        let addr = listener.local_addr().expect("Failed to get test server address");
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().expect("Failed to accept connection");
+                let mut request = vec![];
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).expect("Failed to read request");
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let _ = tx.send(String::from_utf8_lossy(&request).to_lowercase());
+                stream.write_all(&response).expect("Failed to write response");
+            }
+        });
+        (format!("http://{}", addr), rx)
+    }
+
+    // serve_gzip serves a single gzip compressed response for the given test data and returns
+    // the update url to request it from.
+    fn serve_gzip(fname: &str) -> (String, Receiver<String>) {
+        let html = std::fs::read(format!(
+            "{}/resources/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            fname
+        ))
+        .expect("Failed to load test data");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&html).expect("Failed to compress test data");
+        let body = encoder.finish().expect("Failed to compress test data");
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(&body);
+        let (url, rx) = serve(vec![response]);
+        (format!("{}/ScopedViewInline.aspx?updateid=", url), rx)
+    }
+
+    // fixture_response returns an uncompressed response for the given test data.
+    fn fixture_response(fname: &str) -> Vec<u8> {
+        let html = std::fs::read(format!(
+            "{}/resources/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            fname
+        ))
+        .expect("Failed to load test data");
+        html_response(&html)
+    }
+
+    fn html_response(html: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            html.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(html);
+        response
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_compressed() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve_gzip("msuc_update_details.html");
+        client.config.update_url = url;
+        let update = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await;
+        assert!(update.is_ok(), "Expected the compressed update to parse: {:?}", update.err());
+        assert_eq!("5025305", update.unwrap().kb);
+        let request = requests.recv().expect("Expected a request to be received");
+        assert!(request.contains("accept-encoding: gzip"), "Expected gzip to be requested");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_compressed() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve_gzip("msuc_update_details.html");
+        client.config.update_url = url;
+        let update = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"));
+        assert!(update.is_ok(), "Expected the compressed update to parse: {:?}", update.err());
+        assert_eq!("5025305", update.unwrap().kb);
+        let request = requests.recv().expect("Expected a request to be received");
+        assert!(request.contains("accept-encoding: gzip"), "Expected gzip to be requested");
+    }
+
+    fn redirect_responses() -> Vec<Vec<u8>> {
+        vec![
+            b"HTTP/1.1 302 Found\r\nLocation: /cdn/windows10.0-kb5025305-x64.msu\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 331559731\r\nConnection: close\r\n\r\n".to_vec(),
+        ]
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_resolve_download_redirect() {
+        let client = Client::new().expect("Failed to create client");
+        let (base, requests) = serve(redirect_responses());
+        let url = Url::parse(&format!("{}/d/msdownload/update/windows10.0-kb5025305-x64.msu", base))
+            .expect("Failed to parse test url");
+        let resolved = client.resolve_download_redirect(&url).await.expect("Failed to resolve redirect");
+        assert_eq!(format!("{}/cdn/windows10.0-kb5025305-x64.msu", base), resolved.url.as_str());
+        assert_eq!(Some(331559731), resolved.content_length);
+        assert!(requests.recv().expect("Expected a request").starts_with("head "));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_resolve_download_redirect() {
+        let client = Client::new().expect("Failed to create client");
+        let (base, requests) = serve(redirect_responses());
+        let url = Url::parse(&format!("{}/d/msdownload/update/windows10.0-kb5025305-x64.msu", base))
+            .expect("Failed to parse test url");
+        let resolved = client.resolve_download_redirect(&url).expect("Failed to resolve redirect");
+        assert_eq!(format!("{}/cdn/windows10.0-kb5025305-x64.msu", base), resolved.url.as_str());
+        assert_eq!(Some(331559731), resolved.content_length);
+        assert!(requests.recv().expect("Expected a request").starts_with("head "));
+    }
+
+    #[test]
+    fn test_select_latest_per_kb() {
+        let html = std::fs::read_to_string(format!(
+            "{}/resources/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            "msuc_double_digit_rows.html"
+        ))
+        .expect("Failed to load test data");
+        let (_, mut results) = parse_search_results(&html, &SearchFormFields::default())
+            .expect("Failed to parse test data")
+            .expect("Expected test data to contain results");
+        let mut newer = results[5].clone();
+        newer.kb = Some("5030300".to_string());
+        newer.id = "ffffffff-0000-0000-0000-000000000000".parse().expect("Failed to parse update id");
+        let mut older = newer.clone();
+        older.id = "00000000-0000-0000-0000-000000000000".parse().expect("Failed to parse update id");
+        older.last_modified = older.last_modified.pred_opt().expect("Failed to create test date");
+        results.push(older);
+        results.push(newer);
+
+        // a shorter KB number is ordered by its value rather than its text
+        let mut short = results[5].clone();
+        short.kb = Some("958644".to_string());
+        short.id = "11111111-0000-0000-0000-000000000000".parse().expect("Failed to parse update id");
+        results.push(short);
+
+        let latest = select_latest_per_kb(results);
+        assert_eq!(3, latest.len());
+        assert_eq!("11111111-0000-0000-0000-000000000000", latest[0].id);
+        // every result for KB5030211 has the same date, so the lowest id wins
+        assert_eq!("0aec0f4e-5228-4f59-bfc4-08e3c3cd32bb", latest[1].id);
+        assert_eq!("ffffffff-0000-0000-0000-000000000000", latest[2].id);
+    }
+
+    #[test]
+    fn test_is_same_kb() {
+        for kb in ["5030211", "KB5030211", "kb5030211", "Kb5030211", "kB5030211", " KB5030211 "] {
+            assert!(is_same_kb(kb, "5030211"), "{}", kb);
+        }
+        assert!(!is_same_kb("KB5030211", "KB503021"));
+    }
+
+    #[test]
+    fn test_get_retry_delay() {
+        let backoff = Duration::from_secs(5);
+        let max_delay = Duration::from_secs(300);
+        let test_cases = [
+            (None, backoff),
+            (Some("120"), Duration::from_secs(120)),
+            (Some("86400"), max_delay),
+            (Some("Wed, 21 Oct 2015 07:28:00 GMT"), Duration::ZERO),
+            (Some("Fri, 01 Jan 2100 00:00:00 GMT"), max_delay),
+            (Some("soon"), backoff),
+        ];
+        for tc in test_cases.iter() {
+            let mut headers = reqwest::header::HeaderMap::new();
+            if let Some(v) = tc.0 {
+                headers.insert(
+                    reqwest::header::RETRY_AFTER,
+                    v.parse().expect("Failed to parse test header"),
+                );
+            }
+            assert_eq!(tc.1, get_retry_delay(&headers, backoff, max_delay));
+        }
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        let date = (chrono::Utc::now() + chrono::Duration::seconds(60)).to_rfc2822();
+        headers.insert(reqwest::header::RETRY_AFTER, date.parse().expect("Failed to parse test header"));
+        let delay = get_retry_delay(&headers, backoff, max_delay);
+        assert!(delay > Duration::from_secs(50) && delay <= Duration::from_secs(60));
+    }
+
+    fn throttled_responses() -> Vec<Vec<u8>> {
+        vec![
+            b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+            b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+            update_details_response(),
+        ]
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_retries_throttled_requests() {
+        let mut client = Client::builder()
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(10))
+            .build()
+            .expect("Failed to create client");
+        let (url, _) = serve(throttled_responses());
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let update = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await;
+        assert!(update.is_ok(), "Expected the retried update to parse: {:?}", update.err());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_retries_throttled_requests() {
+        let mut client = Client::builder()
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(10))
+            .build()
+            .expect("Failed to create client");
+        let (url, _) = serve(throttled_responses());
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let update = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"));
+        assert!(update.is_ok(), "Expected the retried update to parse: {:?}", update.err());
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_with_stats() {
+        let mut client = Client::builder()
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(10))
+            .build()
+            .expect("Failed to create client");
+        // the 503 response has no Retry-After header, so the client waits for the backoff
+        let (url, _) = serve(vec![throttled_responses().swap_remove(1), update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let (_, stats) = client
+            .get_update_with_stats(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .await
+            .expect("Failed to get update");
+        assert_eq!(2, stats.attempts);
+        assert!(stats.total_wait > Duration::ZERO);
+
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let (_, stats) = client
+            .get_update_with_stats(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .await
+            .expect("Failed to get update");
+        assert_eq!(RequestStats { attempts: 1, total_wait: Duration::ZERO }, stats);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_with_stats() {
+        let mut client = Client::builder()
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(10))
+            .build()
+            .expect("Failed to create client");
+        // the 503 response has no Retry-After header, so the client waits for the backoff
+        let (url, _) = serve(vec![throttled_responses().swap_remove(1), update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let (_, stats) = client
+            .get_update_with_stats(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .expect("Failed to get update");
+        assert_eq!(2, stats.attempts);
+        assert!(stats.total_wait > Duration::ZERO);
+
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let (_, stats) = client
+            .get_update_with_stats(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .expect("Failed to get update");
+        assert_eq!(RequestStats { attempts: 1, total_wait: Duration::ZERO }, stats);
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_write_csv_records() {
+        let html = std::fs::read_to_string(format!(
+            "{}/resources/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            "msuc_small_result.html"
+        ))
+        .expect("Failed to load test data");
+        let (_, results) = parse_search_results(&html, &SearchFormFields::default())
+            .expect("Failed to parse test data")
+            .expect("Expected test data to contain results");
+        let mut writer = csv::Writer::from_writer(vec![]);
+        write_csv_header(&mut writer).expect("Failed to write header");
+        let count = write_csv_records(&mut writer, &results).expect("Failed to write records");
+        let csv = String::from_utf8(writer.into_inner().expect("Failed to flush writer"))
+            .expect("Expected CSV to be valid UTF-8");
+        let mut lines = csv.lines();
+
+        assert_eq!(3, count);
+        assert_eq!(Some("title,id,kb,product,classification,last_modified,version,size"), lines.next());
+        assert_eq!(
+            Some("Security Update For Exchange Server 2019 CU12 (KB5030524),56a97db8-1478-4860-a935-7996c78d10be,5030524,Exchange Server 2019,Security Updates,2023-08-15,,168715878"),
+            lines.next()
+        );
+        assert_eq!(2, lines.count());
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_retries_blank_updates() {
+        let mut client = Client::builder().retry_blank_updates(true).build().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            blank_update_details_response(),
+            update_details_response(),
+            blank_update_details_response(),
+            blank_update_details_response(),
+            blank_update_details_response(),
+            update_details_response(),
+            blank_update_details_response(),
+            update_details_response(),
+        ]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let update = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await.expect("Failed to get update");
+        assert!(!update.title.is_empty());
+        let res = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await;
+        assert!(matches!(res, Err(Error::Parsing(_))));
+        // the other update details methods retry blank pages too
+        let summary = client.summarize(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await.expect("Failed to summarize");
+        assert!(!summary.title.is_empty());
+        let (_, stats) = client
+            .get_update_with_stats(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await
+            .expect("Failed to get update");
+        assert_eq!(2, stats.attempts);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_retries_blank_updates() {
+        let mut client = Client::builder().retry_blank_updates(true).build().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            blank_update_details_response(),
+            update_details_response(),
+            blank_update_details_response(),
+            blank_update_details_response(),
+            blank_update_details_response(),
+            update_details_response(),
+            blank_update_details_response(),
+            update_details_response(),
+        ]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let update = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).expect("Failed to get update");
+        assert!(!update.title.is_empty());
+        let res = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"));
+        assert!(matches!(res, Err(Error::Parsing(_))));
+        // the other update details methods retry blank pages too
+        let summary = client.summarize(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).expect("Failed to summarize");
+        assert!(!summary.title.is_empty());
+        let (_, stats) = client
+            .get_update_with_stats(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .expect("Failed to get update");
+        assert_eq!(2, stats.attempts);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_max_response_bytes() {
+        let mut client = Client::builder().max_response_bytes(1024).build().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response(), search_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let res = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await;
+        assert!(matches!(res, Err(Error::ResponseTooLarge(1024))));
+        let mut stream = client.search("KB5030524").expect("Failed to create search stream");
+        assert!(matches!(stream.next().await, Err(Error::ResponseTooLarge(1024))));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_max_response_bytes() {
+        let mut client = Client::builder().max_response_bytes(1024).build().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response(), search_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let res = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"));
+        assert!(matches!(res, Err(Error::ResponseTooLarge(1024))));
+        let mut stream = client.search("KB5030524").expect("Failed to create search stream");
+        assert!(matches!(stream.next(), Err(Error::ResponseTooLarge(1024))));
+    }
+
+    fn parse_update_id(s: &str) -> UpdateId {
+        s.parse().expect("Failed to parse update id")
+    }
+
+    fn update_details_response() -> Vec<u8> {
+        fixture_response("msuc_update_details.html")
+    }
+
+    // blank_update_details_response returns the update details page as the catalog sometimes
+    // serves it half-rendered, without a title or update id.
+    fn blank_update_details_response() -> Vec<u8> {
+        let html = std::fs::read_to_string(format!(
+            "{}/resources/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            "msuc_update_details.html"
+        ))
+        .expect("Failed to load test data")
+        .replace(
+            "2023-04 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5025305)</span>",
+            "</span>",
+        )
+        .replace("1b0b70c0-191e-42f6-8808-c1b50deacb3b</span>", "</span>");
+        html_response(html.as_bytes())
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_same_lineage() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let res = client.same_lineage(
+            &parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"),
+            &parse_update_id("03423c5a-458d-4cbe-b67e-d47bec7f3fb6"),
+            1,
+        ).await;
+        assert!(res.expect("Expected lineage check to succeed"));
+        assert_eq!(1, requests.try_iter().count());
+        let id = parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b");
+        assert!(client.same_lineage(&id, &id, 0).await.expect("Expected lineage check to succeed"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_same_lineage() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let res = client.same_lineage(
+            &parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"),
+            &parse_update_id("03423c5a-458d-4cbe-b67e-d47bec7f3fb6"),
+            1,
+        );
+        assert!(res.expect("Expected lineage check to succeed"));
+        assert_eq!(1, requests.try_iter().count());
+        let id = parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b");
+        assert!(client.same_lineage(&id, &id, 0).expect("Expected lineage check to succeed"));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_supersedence_timeline() {
+        let mut client = Client::new().expect("Failed to create client");
+        // every update is served the same page, so the second level links back to updates that
+        // were already retrieved
+        let (url, requests) = serve(vec![update_details_response(); 10]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let timeline = client
+            .supersedence_timeline(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"), 2)
+            .await
+            .expect("Failed to get timeline");
+        assert_eq!(10, timeline.len());
+        assert_eq!(10, requests.try_iter().count());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_supersedence_timeline() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response(); 10]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let timeline = client
+            .supersedence_timeline(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"), 2)
+            .expect("Failed to get timeline");
+        assert_eq!(10, timeline.len());
+        assert_eq!(10, requests.try_iter().count());
+    }
+
+    #[test]
+    fn test_skip_failed_page() {
+        let html = std::fs::read_to_string(format!(
+            "{}/resources/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            "msuc_search_with_next_page.html"
+        ))
+        .expect("Failed to load test data");
+        let meta = parse_search_page_meta(&html, &SearchFormFields::default()).expect("Failed to parse test data");
+        // break the date of the first row so the results fail to parse
+        let broken = html.replacen("9/26/2023", "2023-09-26T00:00:00", 1);
+        assert_ne!(html, broken, "Expected the test data to contain the date");
+
+        let client = Client::new().expect("Failed to create client");
+        let mut stream = client.search("2023-04").expect("Failed to create search stream");
+        assert!(stream.process_search_page(&broken).is_err());
+        assert!(!stream.has_next_page());
+        assert!(stream.skip_failed_page());
+        assert!(stream.has_next_page());
+        assert_eq!(meta.view_state, stream.meta.view_state);
+        // the failed page can only be skipped once
+        assert!(!stream.skip_failed_page());
+
+        let error_html = std::fs::read_to_string(format!(
+            "{}/resources/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            "msuc_search_error_500.html"
+        ))
+        .expect("Failed to load test data");
+        let mut stream = client.search("2023-04").expect("Failed to create search stream");
+        assert!(stream.process_search_page(&error_html).is_err());
+        assert!(!stream.skip_failed_page());
+        assert!(!stream.has_next_page());
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_document() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let document = client
+            .get_update_document(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .await
+            .expect("Failed to get update document");
+        let selector = scraper::Selector::parse("#ScopedViewHandler_UpdateID").expect("Failed to parse selector");
+        let id: String = document.select(&selector).next().expect("Expected the id element").text().collect();
+        assert_eq!("1b0b70c0-191e-42f6-8808-c1b50deacb3b", id.trim());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_document() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let document = client
+            .get_update_document(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .expect("Failed to get update document");
+        let selector = scraper::Selector::parse("#ScopedViewHandler_UpdateID").expect("Failed to parse selector");
+        let id: String = document.select(&selector).next().expect("Expected the id element").text().collect();
+        assert_eq!("1b0b70c0-191e-42f6-8808-c1b50deacb3b", id.trim());
+    }
+
+    fn search_response() -> Vec<u8> {
+        fixture_response("msuc_small_result.html")
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_resolve_kbs() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            search_response(),
+            b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        // resolve one at a time so the responses are served in order
+        let resolved = client.resolve_kbs(&["KB5030524"]).await;
+        assert_eq!(1, resolved.len());
+        assert_eq!("KB5030524", resolved[0].0);
+        assert_eq!(3, resolved[0].1.as_ref().expect("Expected the KB to resolve").len());
+        let resolved = client.resolve_kbs(&["5030525"]).await;
+        assert!(resolved[0].1.is_err());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_resolve_kbs() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            search_response(),
+            b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let resolved = client.resolve_kbs(&["KB5030524", "5030525"]);
+        assert_eq!(2, resolved.len());
+        assert_eq!("KB5030524", resolved[0].0);
+        assert_eq!(3, resolved[0].1.as_ref().expect("Expected the KB to resolve").len());
+        assert!(resolved[1].1.is_err());
+    }
+
+    #[cfg(all(feature = "cassette", not(feature = "blocking")))]
+    #[tokio::test]
+    async fn test_record_and_replay_cassette() {
+        let path = std::env::temp_dir().join(format!("msuc-cassette-{}.json", std::process::id()));
+        let (url, _) = serve(vec![update_details_response(), search_response()]);
+        let mut client = Client::builder().record_to(&path).build().expect("Failed to create client");
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let update = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await.expect("Failed to get update");
+        let results = client.search("KB5030524").expect("Failed to create search stream").next().await.expect("Failed to search");
+
+        // the server only serves the recorded responses once, so these must be replayed
+        let mut replay = Client::builder()
+            .retry_blank_updates(true)
+            .replay_from(&path)
+            .build()
+            .expect("Failed to load cassette");
+        assert!(replay.config.retry_blank_updates);
+        replay.config.update_url = client.config.update_url.clone();
+        replay.config.search_url = client.config.search_url.clone();
+        assert_eq!(update, replay.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await.expect("Failed to replay update"));
+        assert_eq!(results, replay.search("KB5030524").expect("Failed to create search stream").next().await.expect("Failed to replay search"));
+        assert!(replay.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await.is_err());
+        std::fs::remove_file(&path).expect("Failed to remove cassette");
+    }
+
+    #[cfg(all(feature = "cassette", feature = "blocking"))]
+    #[test]
+    fn test_record_and_replay_cassette() {
+        let path = std::env::temp_dir().join(format!("msuc-cassette-blocking-{}.json", std::process::id()));
+        let (url, _) = serve(vec![update_details_response(), search_response()]);
+        let mut client = Client::builder().record_to(&path).build().expect("Failed to create client");
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let update = client.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).expect("Failed to get update");
+        let results = client.search("KB5030524").expect("Failed to create search stream").next().expect("Failed to search");
+
+        // the server only serves the recorded responses once, so these must be replayed
+        let mut replay = Client::builder()
+            .retry_blank_updates(true)
+            .replay_from(&path)
+            .build()
+            .expect("Failed to load cassette");
+        assert!(replay.config.retry_blank_updates);
+        replay.config.update_url = client.config.update_url.clone();
+        replay.config.search_url = client.config.search_url.clone();
+        assert_eq!(update, replay.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).expect("Failed to replay update"));
+        assert_eq!(results, replay.search("KB5030524").expect("Failed to create search stream").next().expect("Failed to replay search"));
+        assert!(replay.get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).is_err());
+        std::fs::remove_file(&path).expect("Failed to remove cassette");
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_all() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            fixture_response("msuc_small_result.html"),
+            fixture_response("msuc_small_result.html"),
+            fixture_response("msuc_search_too_many_results.html"),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        assert_eq!(3, client.search_all("KB5030524", 100).await.expect("Failed to search").len());
+        assert_eq!(2, client.search_all("KB5030524", 2).await.expect("Failed to search").len());
+        assert!(client.search_all("cumulative", 100).await.is_err());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_all() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            fixture_response("msuc_small_result.html"),
+            fixture_response("msuc_small_result.html"),
+            fixture_response("msuc_search_too_many_results.html"),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        assert_eq!(3, client.search_all("KB5030524", 100).expect("Failed to search").len());
+        assert_eq!(2, client.search_all("KB5030524", 2).expect("Failed to search").len());
+        assert!(client.search_all("cumulative", 100).is_err());
+    }
+
+    // MockCatalog serves a single canned page of results and fails every other call.
+    struct MockCatalog;
+
+    struct MockStream {
+        pages: Vec<Vec<SearchResult>>,
+    }
+
+    fn mock_result(id: &str) -> SearchResult {
+        SearchResult {
+            title: format!("Update {}", id),
+            id: id.parse().expect("Failed to parse update id"),
+            kb: Some("5030524".to_string()),
+            product: "Windows 11".to_string(),
+            classification: "Security Updates".to_string(),
+            last_modified: chrono::NaiveDate::from_ymd_opt(2023, 9, 26).expect("Failed to create date"),
+            last_modified_raw: "9/26/2023".to_string(),
+            version: None,
+            size: 0,
+        }
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[async_trait]
+    impl SearchResultsStreamer for MockStream {
+        async fn next(&mut self) -> Result<Option<Vec<SearchResult>>, Error> {
+            Ok(self.pages.pop())
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    impl SearchResultsStreamer for MockStream {
+        fn next(&mut self) -> Result<Option<Vec<SearchResult>>, Error> {
+            Ok(self.pages.pop())
+        }
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[async_trait]
+    impl Catalog for MockCatalog {
+        type Stream = MockStream;
+
+        fn search(&self, _query: &str) -> Result<Self::Stream, Error> {
+            Ok(MockStream { pages: vec![vec![mock_result("1b0b70c0-191e-42f6-8808-c1b50deacb3b"), mock_result("03423c5a-458d-4cbe-b67e-d47bec7f3fb6")]] })
+        }
+
+        async fn get_update(&self, update_id: &UpdateId) -> Result<Update, Error> {
+            Err(Error::Search(format!("No update {}", update_id)))
+        }
+
+        async fn resolve_download_redirect(&self, url: &Url) -> Result<ResolvedDownload, Error> {
+            Err(Error::Internal(format!("No download {}", url)))
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    impl Catalog for MockCatalog {
+        type Stream = MockStream;
+
+        fn search(&self, _query: &str) -> Result<Self::Stream, Error> {
+            Ok(MockStream { pages: vec![vec![mock_result("1b0b70c0-191e-42f6-8808-c1b50deacb3b"), mock_result("03423c5a-458d-4cbe-b67e-d47bec7f3fb6")]] })
+        }
+
+        fn get_update(&self, update_id: &UpdateId) -> Result<Update, Error> {
+            Err(Error::Search(format!("No update {}", update_id)))
+        }
+
+        fn resolve_download_redirect(&self, url: &Url) -> Result<ResolvedDownload, Error> {
+            Err(Error::Internal(format!("No download {}", url)))
+        }
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    async fn count_results<C: Catalog>(catalog: &C, query: &str) -> Result<usize, Error> {
+        let mut stream = catalog.search(query)?;
+        let mut count = 0;
+        while let Some(page) = stream.next().await? {
+            count += page.len();
+        }
+        Ok(count)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn count_results<C: Catalog>(catalog: &C, query: &str) -> Result<usize, Error> {
+        let mut stream = catalog.search(query)?;
+        let mut count = 0;
+        while let Some(page) = stream.next()? {
+            count += page.len();
+        }
+        Ok(count)
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_catalog_mock() {
+        assert_eq!(2, count_results(&MockCatalog, "KB5030524").await.expect("Failed to search"));
+        assert!(Catalog::get_update(&MockCatalog, &parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await.is_err());
+
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_small_result.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        assert_eq!(3, count_results(&client, "KB5030524").await.expect("Failed to search"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_catalog_mock() {
+        assert_eq!(2, count_results(&MockCatalog, "KB5030524").expect("Failed to search"));
+        assert!(Catalog::get_update(&MockCatalog, &parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).is_err());
+
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_small_result.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        assert_eq!(3, count_results(&client, "KB5030524").expect("Failed to search"));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_stream_counts_after_first_page() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_search_with_next_page.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client.search("cumulative").expect("Failed to create search stream");
+        assert_eq!((0, 0, 0), (stream.result_count(), stream.page_count(), stream.current_page()));
+        stream.next().await.expect("Failed to get next page");
+        assert_eq!((761, 31, 1), (stream.result_count(), stream.page_count(), stream.current_page()));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_stream_counts_after_first_page() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_search_with_next_page.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client.search("cumulative").expect("Failed to create search stream");
+        assert_eq!((0, 0, 0), (stream.result_count(), stream.page_count(), stream.current_page()));
+        stream.next().expect("Failed to get next page");
+        assert_eq!((761, 31, 1), (stream.result_count(), stream.page_count(), stream.current_page()));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_meta_charset() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_update_details_windows1252.html")]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let update = client
+            .get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .await
+            .expect("Failed to get update");
+        assert!(update.description.contains("Windows\u{ae} 11 \u{2013} including Fran\u{e7}ais and Portugu\u{ea}s"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_meta_charset() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_update_details_windows1252.html")]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let update = client
+            .get_update(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .expect("Failed to get update");
+        assert!(update.description.contains("Windows\u{ae} 11 \u{2013} including Fran\u{e7}ais and Portugu\u{ea}s"));
+    }
+
+    #[test]
+    fn test_client_config() {
+        let client = Client::new().expect("Failed to create client");
+        assert_eq!(&ClientConfig::default(), client.config());
+
+        let client = Client::builder()
+            .user_agent("test-agent")
+            .max_retries(3)
+            .max_concurrent_requests(0)
+            .cookie_store(false)
+            .build()
+            .expect("Failed to create client");
+        let config = client.config();
+        assert_eq!("test-agent", config.user_agent);
+        assert_eq!(3, config.max_retries);
+        assert_eq!(Some(1), config.max_concurrent_requests);
+        assert!(!config.cookie_store);
+        assert_eq!("https://www.catalog.update.microsoft.com/Search.aspx", config.search_url);
+    }
+
+    #[test]
+    fn test_client_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Client>();
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_owned() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let client = Arc::new(client);
+        let mut join_set = tokio::task::JoinSet::new();
+        join_set.spawn(client.clone().get_update_owned(parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")));
+        let update = join_set
+            .join_next()
+            .await
+            .expect("Expected a task")
+            .expect("Failed to join task")
+            .expect("Failed to get update");
+        assert_eq!("5025305", update.kb);
+    }
+
+    #[test]
+    fn test_parse_update_from_bytes() {
+        let client = Client::new().expect("Failed to create client");
+        let html = std::fs::read(format!(
+            "{}/resources/test/msuc_update_details.html",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .expect("Failed to load test data");
+        let update = client.parse_update_from_bytes(&html).expect("Failed to parse update");
+        let with_bom = [b"\xEF\xBB\xBF".as_slice(), &html].concat();
+        assert_eq!(
+            update,
+            client.parse_update_from_bytes(&with_bom).expect("Failed to parse update with BOM")
+        );
+        assert!(matches!(
+            client.parse_update_from_bytes(&[0xff, 0xfe]),
+            Err(Error::Parsing(_))
+        ));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_since() {
+        let mut client = Client::new().expect("Failed to create client");
+        // the page links a next page, which is not requested as the page reaches older results
+        let (url, _) = serve(vec![fixture_response("msuc_search_with_next_page.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let since = chrono::NaiveDate::from_ymd_opt(2023, 9, 20).expect("Failed to create date");
+        let results = client.search_since("2023-09", since).await.expect("Failed to search");
+        assert_eq!(23, results.len());
+        assert!(results.iter().all(|r| r.last_modified >= since));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_since() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_search_with_next_page.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let since = chrono::NaiveDate::from_ymd_opt(2023, 9, 20).expect("Failed to create date");
+        let results = client.search_since("2023-09", since).expect("Failed to search");
+        assert_eq!(23, results.len());
+        assert!(results.iter().all(|r| r.last_modified >= since));
+    }
+
+    #[test]
+    fn test_collect_since_unordered() {
+        let html = std::fs::read_to_string(format!(
+            "{}/resources/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            "msuc_search_too_many_results.html"
+        ))
+        .expect("Failed to load test data");
+        let page = parse_search_results_only(&html).expect("Failed to parse test data");
+        let since = chrono::NaiveDate::from_ymd_opt(2023, 9, 1).expect("Failed to create date");
+
+        let (mut results, mut seen, mut ordered) = (vec![], HashSet::new(), true);
+        assert!(collect_since(&mut results, &mut seen, &mut ordered, page.clone(), since));
+        assert_eq!(11, results.len());
+
+        // oldest first, so the remaining pages can't be skipped
+        let (mut results, mut seen, mut ordered) = (vec![], HashSet::new(), true);
+        let reversed = page.into_iter().rev().collect::<Vec<_>>();
+        assert!(!collect_since(&mut results, &mut seen, &mut ordered, reversed, since));
+        assert_eq!(11, results.len());
+        assert!(!ordered);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_stream_result_cap() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_search_too_many_results.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client
+            .search("windows")
+            .expect("Failed to create search stream")
+            .with_result_cap(25);
+        let page = stream.next().await.expect("Failed to get next page").expect("Expected results");
+        assert_eq!(25, page.len());
+        assert!(stream.too_many_results());
+        assert!(!stream.result_cap_reached());
+        assert_eq!(None, stream.next().await.expect("Failed to get next page"));
+        assert!(stream.result_cap_reached());
+        assert!(stream.has_next_page());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_stream_result_cap() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_search_too_many_results.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client
+            .search("windows")
+            .expect("Failed to create search stream")
+            .with_result_cap(25);
+        let page = stream.next().expect("Failed to get next page").expect("Expected results");
+        assert_eq!(25, page.len());
+        assert!(stream.too_many_results());
+        assert!(!stream.result_cap_reached());
+        assert_eq!(None, stream.next().expect("Failed to get next page"));
+        assert!(stream.result_cap_reached());
+        assert!(stream.has_next_page());
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_stream_has_next_page_before_first_page() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_search_with_next_page.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client.search("2023-04").expect("Failed to create search stream");
+        assert!(!stream.has_next_page());
+        assert!(stream.next().await.expect("Failed to get next page").is_some());
+        assert!(stream.has_next_page());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_stream_has_next_page_before_first_page() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_search_with_next_page.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client.search("2023-04").expect("Failed to create search stream");
+        assert!(!stream.has_next_page());
+        assert!(stream.next().expect("Failed to get next page").is_some());
+        assert!(stream.has_next_page());
+    }
+
+    #[test]
+    fn test_search_stream_result_cap_limit() {
+        let client = Client::new().expect("Failed to create client");
+        let stream = client
+            .search("windows")
+            .expect("Failed to create search stream")
+            .with_result_cap(5000);
+        assert_eq!(CATALOG_RESULT_LIMIT, stream.result_cap);
+    }
+
+    // with_session_cookie adds a session cookie to a response from `fixture_response`.
+    fn with_session_cookie(response: Vec<u8>) -> Vec<u8> {
+        let status_line = b"HTTP/1.1 200 OK\r\n";
+        [
+            status_line.as_slice(),
+            b"Set-Cookie: ASP.NET_SessionId=msuctest; path=/\r\n",
+            &response[status_line.len()..],
+        ]
+        .concat()
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_stream_keeps_session_cookie() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![
+            with_session_cookie(fixture_response("msuc_search_with_next_page.html")),
+            search_response(),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client.search("cumulative").expect("Failed to create search stream");
+        stream.next().await.expect("Failed to get first page");
+        stream.next().await.expect("Failed to get second page");
+        let first = requests.recv().expect("Failed to receive request");
+        assert!(!first.contains("cookie:"));
+        let second = requests.recv().expect("Failed to receive request");
+        assert!(second.contains("asp.net_sessionid=msuctest"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_stream_keeps_session_cookie() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![
+            with_session_cookie(fixture_response("msuc_search_with_next_page.html")),
+            search_response(),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client.search("cumulative").expect("Failed to create search stream");
+        stream.next().expect("Failed to get first page");
+        stream.next().expect("Failed to get second page");
+        let first = requests.recv().expect("Failed to receive request");
+        assert!(!first.contains("cookie:"));
+        let second = requests.recv().expect("Failed to receive request");
+        assert!(second.contains("asp.net_sessionid=msuctest"));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_stream_next_with_html() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![search_response()]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client.search("KB5030524").expect("Failed to create search stream");
+        let peeked = stream.peek_next().await.expect("Failed to peek next page");
+        let (results, html) = stream
+            .next_with_html()
+            .await
+            .expect("Failed to get next page")
+            .expect("Expected results");
+        assert_eq!(peeked, Some(results.clone()));
+        assert_eq!(parse_search_results_only(&html).expect("Failed to parse page HTML"), results);
+        assert_eq!(None, stream.next_with_html().await.expect("Failed to get next page"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_stream_next_with_html() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![search_response()]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client.search("KB5030524").expect("Failed to create search stream");
+        let peeked = stream.peek_next().expect("Failed to peek next page");
+        let (results, html) = stream
+            .next_with_html()
+            .expect("Failed to get next page")
+            .expect("Expected results");
+        assert_eq!(peeked, Some(results.clone()));
+        assert_eq!(parse_search_results_only(&html).expect("Failed to parse page HTML"), results);
+        assert_eq!(None, stream.next_with_html().expect("Failed to get next page"));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_stream_deadline() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_search_with_next_page.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client
+            .search("cumulative")
+            .expect("Failed to create search stream")
+            .with_deadline(Instant::now() + Duration::from_secs(60));
+        assert!(stream.next().await.expect("Failed to get next page").is_some());
+        assert!(!stream.deadline_reached());
+        let mut stream = stream.with_deadline(Instant::now());
+        assert_eq!(None, stream.next().await.expect("Failed to get next page"));
+        assert!(stream.deadline_reached());
+        assert!(stream.has_next_page());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_stream_deadline() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![fixture_response("msuc_search_with_next_page.html")]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let mut stream = client
+            .search("cumulative")
+            .expect("Failed to create search stream")
+            .with_deadline(Instant::now() + Duration::from_secs(60));
+        assert!(stream.next().expect("Failed to get next page").is_some());
+        assert!(!stream.deadline_reached());
+        let mut stream = stream.with_deadline(Instant::now());
+        assert_eq!(None, stream.next().expect("Failed to get next page"));
+        assert!(stream.deadline_reached());
+        assert!(stream.has_next_page());
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_current_only() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            search_response(),
+            update_details_response(),
+            fixture_response("msuc_update_details_never_restarts.html"),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let updates = client.search_current_only("KB5030524", 2).await.expect("Failed to search");
+        assert_eq!(1, updates.len());
+        assert!(updates[0].superseded_by.is_empty());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_current_only() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            search_response(),
+            update_details_response(),
+            fixture_response("msuc_update_details_never_restarts.html"),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let updates = client.search_current_only("KB5030524", 2).expect("Failed to search");
+        assert_eq!(1, updates.len());
+        assert!(updates[0].superseded_by.is_empty());
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_timed() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let (update, timing) = client
+            .get_update_timed(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .await
+            .expect("Failed to get update");
+        assert_eq!("5025305", update.kb);
+        assert_eq!(timing.total, timing.request + timing.parse);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_timed() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let (update, timing) = client
+            .get_update_timed(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .expect("Failed to get update");
+        assert_eq!("5025305", update.kb);
+        assert_eq!(timing.total, timing.request + timing.parse);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_all_capped() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            fixture_response("msuc_search_too_many_results.html"),
+            fixture_response("msuc_small_result.html"),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let (results, capped) = client.search_all_capped("cumulative", 10).await.expect("Failed to search");
+        assert_eq!(10, results.len());
+        assert!(capped);
+        let (results, capped) = client.search_all_capped("KB5030524", 100).await.expect("Failed to search");
+        assert_eq!(3, results.len());
+        assert!(!capped);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_all_capped() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            fixture_response("msuc_search_too_many_results.html"),
+            fixture_response("msuc_small_result.html"),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let (results, capped) = client.search_all_capped("cumulative", 10).expect("Failed to search");
+        assert_eq!(10, results.len());
+        assert!(capped);
+        let (results, capped) = client.search_all_capped("KB5030524", 100).expect("Failed to search");
+        assert_eq!(3, results.len());
+        assert!(!capped);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_with_details() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            search_response(),
+            update_details_response(),
+            update_details_response(),
+            update_details_response(),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let updates = client.search_with_details("KB5030524", 2).collect::<Vec<_>>().await;
+        assert_eq!(3, updates.len());
+        assert!(updates.iter().all(|u| u.is_ok()));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_with_details() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            search_response(),
+            update_details_response(),
+            update_details_response(),
+            update_details_response(),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let updates = client.search_with_details("KB5030524", 2).collect::<Vec<_>>();
+        assert_eq!(3, updates.len());
+        assert!(updates.iter().all(|u| u.is_ok()));
+    }
+
+    fn server_error_response() -> Vec<u8> {
+        b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec()
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_enriched() {
+        let mut client = Client::builder()
+            .retry_backoff(Duration::from_millis(10))
+            .build()
+            .expect("Failed to create client");
+        let (url, _) = serve(vec![
+            server_error_response(),
+            search_response(),
+            server_error_response(),
+            update_details_response(),
+            update_details_response(),
+            update_details_response(),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let updates = client.search_enriched("KB5030524", 1, 1).collect::<Vec<_>>().await;
+        assert_eq!(3, updates.len());
+        assert!(updates.iter().all(|u| u.is_ok()));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_enriched() {
+        let mut client = Client::builder()
+            .retry_backoff(Duration::from_millis(10))
+            .build()
+            .expect("Failed to create client");
+        let (url, _) = serve(vec![
+            server_error_response(),
+            search_response(),
+            server_error_response(),
+            update_details_response(),
+            update_details_response(),
+            update_details_response(),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let updates = client.search_enriched("KB5030524", 1, 1).collect::<Vec<_>>();
+        assert_eq!(3, updates.len());
+        assert!(updates.iter().all(|u| u.is_ok()));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_into_channel() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![search_response()]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        // a capacity below the number of results makes the search wait on the receiver
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let search = client.search_into_channel("KB5030524", tx);
+        let receive = async {
+            let mut results = vec![];
+            while let Some(r) = rx.recv().await {
+                results.push(r);
+            }
+            results
+        };
+        let (_, results) = tokio::join!(search, receive);
+        assert_eq!(3, results.len());
+        assert!(results.iter().all(|r| r.is_ok()));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_query_too_broad() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            fixture_response("msuc_search_too_many_results.html"),
+            search_response(),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        assert!(client.query_too_broad("windows").await.expect("Failed to check query"));
+        assert!(!client.query_too_broad("KB5030524").await.expect("Failed to check query"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_query_too_broad() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![
+            fixture_response("msuc_search_too_many_results.html"),
+            search_response(),
+        ]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        assert!(client.query_too_broad("windows").expect("Failed to check query"));
+        assert!(!client.query_too_broad("KB5030524").expect("Failed to check query"));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_for() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response(), update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let mut result = mock_result("1b0b70c0-191e-42f6-8808-c1b50deacb3b");
+        let update = client.get_update_for(&result).await.expect("Failed to get update");
+        assert_eq!(Some(result.title.clone()), update.search_title);
+        result.title = update.title;
+        let update = client.get_update_for(&result).await.expect("Failed to get update");
+        assert_eq!(None, update.search_title);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_for() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response(), update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let mut result = mock_result("1b0b70c0-191e-42f6-8808-c1b50deacb3b");
+        let update = client.get_update_for(&result).expect("Failed to get update");
+        assert_eq!(Some(result.title.clone()), update.search_title);
+        result.title = update.title;
+        let update = client.get_update_for(&result).expect("Failed to get update");
+        assert_eq!(None, update.search_title);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_estimate_total_size() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response(), update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let size = summarize_fixture_size();
+        let total = client.estimate_total_size(&[
+            parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"),
+            parse_update_id("03423c5a-458d-4cbe-b67e-d47bec7f3fb6"),
+            parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"),
+        ]).await.expect("Failed to estimate size");
+        assert_eq!(size * 2, total);
+        assert_eq!(2, requests.try_iter().count());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_estimate_total_size() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response(), update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let size = summarize_fixture_size();
+        let total = client.estimate_total_size(&[
+            parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"),
+            parse_update_id("03423c5a-458d-4cbe-b67e-d47bec7f3fb6"),
+            parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"),
+        ]).expect("Failed to estimate size");
+        assert_eq!(size * 2, total);
+        assert_eq!(2, requests.try_iter().count());
+    }
+
+    fn summarize_fixture_size() -> u64 {
+        let html = std::fs::read_to_string(format!(
+            "{}/resources/test/msuc_update_details.html",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .expect("Failed to load test data");
+        parse_update_summary(&html).expect("Failed to parse update summary").size
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_for_superseded_by() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let superseded_by = SupersededByUpdate {
+            title: "2023-09 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5030219)".to_string(),
+            kb: Some("5030219".to_string()),
+            id: "03423c5a-458d-4cbe-b67e-d47bec7f3fb6".parse().expect("Failed to parse update id"),
+            release_month: chrono::NaiveDate::from_ymd_opt(2023, 9, 1),
+        };
+        client.get_update_for_superseded_by(&superseded_by).await.expect("Failed to get update");
+        let request = requests.recv().expect("Failed to receive request");
+        assert!(request.contains("updateid=03423c5a-458d-4cbe-b67e-d47bec7f3fb6"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_for_superseded_by() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let superseded_by = SupersededByUpdate {
+            title: "2023-09 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5030219)".to_string(),
+            kb: Some("5030219".to_string()),
+            id: "03423c5a-458d-4cbe-b67e-d47bec7f3fb6".parse().expect("Failed to parse update id"),
+            release_month: chrono::NaiveDate::from_ymd_opt(2023, 9, 1),
+        };
+        client.get_update_for_superseded_by(&superseded_by).expect("Failed to get update");
+        let request = requests.recv().expect("Failed to receive request");
+        assert!(request.contains("updateid=03423c5a-458d-4cbe-b67e-d47bec7f3fb6"));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_max_concurrent_requests() {
+        let mut client = Client::builder()
+            .max_concurrent_requests(1)
+            .build()
+            .expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response(), update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let clone = client.clone();
+        let limiter = client.limiter.clone().expect("Expected a request limiter");
+        let permit = limiter.acquire().await.expect("Failed to acquire permit");
+        let handle = tokio::spawn(async move { clone.summarize(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(0, requests.try_iter().count());
+        drop(permit);
+        handle.await.expect("Failed to join").expect("Failed to summarize");
+        assert!(client.summarize(&parse_update_id("03423c5a-458d-4cbe-b67e-d47bec7f3fb6")).await.is_ok());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_max_concurrent_requests() {
+        let mut client = Client::builder()
+            .max_concurrent_requests(1)
+            .build()
+            .expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response(), update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let clone = client.clone();
+        let limiter = client.limiter.clone().expect("Expected a request limiter");
+        let permit = limiter.acquire().expect("Failed to acquire permit");
+        let handle = std::thread::spawn(move || clone.summarize(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")));
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(0, requests.try_iter().count());
+        drop(permit);
+        handle.join().expect("Failed to join").expect("Failed to summarize");
+        assert!(client.summarize(&parse_update_id("03423c5a-458d-4cbe-b67e-d47bec7f3fb6")).is_ok());
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_download_dialog_raw() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![fixture_response("msuc_small_result.html")]);
+        client.config.download_dialog_url = format!("{}/DownloadDialog.aspx", url);
+        let body = client
+            .get_download_dialog_raw(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .await
+            .expect("Failed to get download dialog");
+        assert!(body.contains("ctl00_catalogBody_searchDuration"));
+        let request = requests.recv().expect("Failed to receive request");
+        assert!(request.starts_with("post /downloaddialog.aspx"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_download_dialog_raw() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![fixture_response("msuc_small_result.html")]);
+        client.config.download_dialog_url = format!("{}/DownloadDialog.aspx", url);
+        let body = client
+            .get_download_dialog_raw(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .expect("Failed to get download dialog");
+        assert!(body.contains("ctl00_catalogBody_searchDuration"));
+        let request = requests.recv().expect("Failed to receive request");
+        assert!(request.starts_with("post /downloaddialog.aspx"));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_search_union() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![search_response(), search_response()]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let results = client.search_union(&["KB5030524", "KB5030524"], 100).await.expect("Failed to search");
+        assert_eq!(3, results.len());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_search_union() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![search_response(), search_response()]);
+        client.config.search_url = format!("{}/Search.aspx", url);
+        let results = client.search_union(&["KB5030524", "KB5030524"], 100).expect("Failed to search");
+        assert_eq!(3, results.len());
+    }
+
+    #[test]
+    fn test_union_results() {
+        let a = mock_result("1b0b70c0-191e-42f6-8808-c1b50deacb3b");
+        let b = mock_result("03423c5a-458d-4cbe-b67e-d47bec7f3fb6");
+        let c = mock_result("10b0cdce-d084-452d-b6a3-318a3ade0a6e");
+        let results = union_results(vec![vec![a.clone(), b.clone()], vec![c.clone(), a.clone()]]);
+        assert_eq!(vec![a, b, c], results);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_self_test() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let err = client.self_test().await.expect_err("Expected self test to fail");
+        assert!(matches!(err, Error::Parsing(ref e) if e.contains("kb was '5025305', expected '958644'")));
+        assert!(requests.recv().expect("Failed to receive request").contains(SELF_TEST_UPDATE_ID));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_self_test() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, requests) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let err = client.self_test().expect_err("Expected self test to fail");
+        assert!(matches!(err, Error::Parsing(ref e) if e.contains("kb was '5025305', expected '958644'")));
+        assert!(requests.recv().expect("Failed to receive request").contains(SELF_TEST_UPDATE_ID));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_fields() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let update = client
+            .get_update_fields(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"), UpdateFields::TITLE)
+            .await
+            .expect("Failed to get update fields");
+        assert!(update.title.is_some());
+        assert_eq!(None, update.kb);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_fields() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let update = client
+            .get_update_fields(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"), UpdateFields::TITLE)
+            .expect("Failed to get update fields");
+        assert!(update.title.is_some());
+        assert_eq!(None, update.kb);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_get_update_freshness() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let freshness = client
+            .get_update_freshness(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .await
+            .expect("Failed to get update freshness");
+        assert_eq!(
+            (chrono::NaiveDate::from_ymd_opt(2023, 4, 25).expect("Failed to create date"), 331559731),
+            freshness
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_get_update_freshness() {
+        let mut client = Client::new().expect("Failed to create client");
+        let (url, _) = serve(vec![update_details_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        let freshness = client
+            .get_update_freshness(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b"))
+            .expect("Failed to get update freshness");
+        assert_eq!(
+            (chrono::NaiveDate::from_ymd_opt(2023, 4, 25).expect("Failed to create date"), 331559731),
+            freshness
+        );
+    }
+
+    // no_content_length_response returns the update details without a Content-Length header, as
+    // for a compressed response, so the body length can only come from reading the body.
+    fn no_content_length_response() -> Vec<u8> {
+        let html = std::fs::read(format!(
+            "{}/resources/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            "msuc_update_details.html"
+        ))
+        .expect("Failed to load test data");
+        let mut response = b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nConnection: close\r\n\r\n".to_vec();
+        response.extend_from_slice(&html);
+        response
+    }
+
+    fn update_details_len() -> u64 {
+        std::fs::metadata(format!(
+            "{}/resources/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            "msuc_update_details.html"
+        ))
+        .expect("Failed to load test data")
+        .len()
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_on_response() {
+        let responses = Arc::new(std::sync::Mutex::new(vec![]));
+        let hook_responses = responses.clone();
+        let mut client = Client::builder()
+            .on_response(Arc::new(move |r: &ResponseInfo| hook_responses.lock().expect("Failed to lock responses").push(r.clone())))
+            .build()
+            .expect("Failed to create client");
+        let (url, _) = serve(vec![no_content_length_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        client.summarize(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).await.expect("Failed to summarize");
+        let responses = responses.lock().expect("Failed to lock responses");
+        assert_eq!(1, responses.len());
+        assert_eq!(("GET", 200), (responses[0].method.as_str(), responses[0].status));
+        assert_eq!(Some(update_details_len()), responses[0].body_len);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_on_response() {
+        let responses = Arc::new(std::sync::Mutex::new(vec![]));
+        let hook_responses = responses.clone();
+        let mut client = Client::builder()
+            .on_response(Arc::new(move |r: &ResponseInfo| hook_responses.lock().expect("Failed to lock responses").push(r.clone())))
+            .build()
+            .expect("Failed to create client");
+        let (url, _) = serve(vec![no_content_length_response()]);
+        client.config.update_url = format!("{}/ScopedViewInline.aspx?updateid=", url);
+        client.summarize(&parse_update_id("1b0b70c0-191e-42f6-8808-c1b50deacb3b")).expect("Failed to summarize");
+        let responses = responses.lock().expect("Failed to lock responses");
+        assert_eq!(1, responses.len());
+        assert_eq!(("GET", 200), (responses[0].method.as_str(), responses[0].status));
+        assert_eq!(Some(update_details_len()), responses[0].body_len);
     }
 }
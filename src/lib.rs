@@ -30,11 +30,11 @@ async fn main() {
                 for r in results {
                     println!("title: {}", r.title);
                     println!("id: {}", r.id);
-                    println!("kb: {}", r.kb);
+                    println!("kb: {}", r.kb.as_deref().unwrap_or(""));
                     println!("product: {}", r.product);
                     println!("classification: {}", r.classification);
                     println!("last modified: {}", r.last_modified);
-                    println!("version: {}", r.version.unwrap_or("".to_string()));
+                    println!("version: {}", r.version.as_deref().unwrap_or(""));
                     println!("size: {}", r.size);
                     println!();
                 }
@@ -92,6 +92,8 @@ The following crate features are available:
 
 - `default`: async/await support
 - `blocking`: blocking support
+- `csv`: CSV export of search results and WSUS-style CSV export of updates
+- `cassette`: record and replay of HTTP interactions for offline tests
 
 > **Note**: The `blocking` feature is mutually exclusive with the `default` feature.
 
@@ -100,4 +102,7 @@ The following crate features are available:
 mod model;
 mod parser;
 mod client;
+mod limiter;
+#[cfg(feature = "cassette")]
+mod cassette;
 pub mod prelude;
\ No newline at end of file
@@ -0,0 +1,100 @@
+#[cfg(feature = "blocking")]
+use std::sync::{Condvar, Mutex};
+use crate::model::Error;
+
+/// `RequestLimiter` caps the number of requests in flight across every clone of a `Client`.
+#[cfg(not(feature = "blocking"))]
+pub struct RequestLimiter {
+    semaphore: tokio::sync::Semaphore,
+}
+
+#[cfg(not(feature = "blocking"))]
+impl RequestLimiter {
+    /// `new` creates a limiter that allows up to `max` requests in flight.
+    pub fn new(max: usize) -> Self {
+        RequestLimiter {
+            semaphore: tokio::sync::Semaphore::new(max),
+        }
+    }
+
+    /// `acquire` waits until a request can be sent. The request counts as in flight until the
+    /// returned permit is dropped.
+    pub async fn acquire(&self) -> Result<tokio::sync::SemaphorePermit<'_>, Error> {
+        self.semaphore
+            .acquire()
+            .await
+            .map_err(|e| Error::Internal(format!("Failed to acquire request permit: {}", e)))
+    }
+}
+
+/// `RequestLimiter` caps the number of requests in flight across every clone of a `Client`.
+#[cfg(feature = "blocking")]
+pub struct RequestLimiter {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+/// `RequestPermit` marks a request as in flight until it is dropped.
+#[cfg(feature = "blocking")]
+pub struct RequestPermit<'a> {
+    limiter: &'a RequestLimiter,
+}
+
+#[cfg(feature = "blocking")]
+impl RequestLimiter {
+    /// `new` creates a limiter that allows up to `max` requests in flight.
+    pub fn new(max: usize) -> Self {
+        RequestLimiter {
+            available: Mutex::new(max),
+            released: Condvar::new(),
+        }
+    }
+
+    /// `acquire` blocks until a request can be sent. The request counts as in flight until the
+    /// returned permit is dropped.
+    pub fn acquire(&self) -> Result<RequestPermit<'_>, Error> {
+        let poisoned = |_| Error::Internal("Request limiter lock poisoned".to_string());
+        let mut available = self.available.lock().map_err(poisoned)?;
+        while *available == 0 {
+            available = self.released.wait(available).map_err(poisoned)?;
+        }
+        *available -= 1;
+        Ok(RequestPermit { limiter: self })
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Drop for RequestPermit<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut available) = self.limiter.available.lock() {
+            *available += 1;
+            self.limiter.released.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_request_limiter() {
+        let limiter = RequestLimiter::new(1);
+        let permit = limiter.acquire().await.expect("Failed to acquire permit");
+        assert!(limiter.semaphore.try_acquire().is_err());
+        drop(permit);
+        assert!(limiter.acquire().await.is_ok());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_request_limiter() {
+        let limiter = RequestLimiter::new(1);
+        let permit = limiter.acquire().expect("Failed to acquire permit");
+        assert_eq!(0, *limiter.available.lock().expect("Failed to lock"));
+        drop(permit);
+        assert_eq!(1, *limiter.available.lock().expect("Failed to lock"));
+        assert!(limiter.acquire().is_ok());
+    }
+}
@@ -1,6 +1,11 @@
 use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 use thiserror::Error;
 use url::Url;
+use crate::parser::{normalize_product, parse_architecture_from_title};
 
 /// `Error` represents an error that can occur while using the MSUC client.
 #[derive(Error, Debug)]
@@ -15,24 +20,75 @@ pub enum Error {
     Internal(String),
     #[error("Microsoft Update Catalog error: {0}, code: {1}")]
     Msuc(String, String),
+    #[error("export error: {0}")]
+    Export(String),
+    #[error("response exceeded the limit of {0} bytes")]
+    ResponseTooLarge(usize),
 }
 
 /// `SearchPage` represents a page of search results and the metadata needed to retrieve the next.
 pub type SearchPage = (SearchPageMeta, Vec<SearchResult>);
 
 /// `SearchResult` represents a single update search result from the Microsoft Update Catalog.
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub struct SearchResult {
     pub title: String,
-    pub id: String,
-    pub kb: String,
+    pub id: UpdateId,
+    /// `kb` is the KB number from the title, or `None` if the title does not include one, as for
+    /// some feature packs and tools.
+    pub kb: Option<String>,
     pub product: String,
     pub classification: String,
     pub last_modified: chrono::NaiveDate,
+    /// `last_modified_raw` is the last updated date exactly as the catalog returned it.
+    pub last_modified_raw: String,
     pub version: Option<String>,
+    /// `size` is the size in bytes, or 0 if the catalog does not list a size.
     pub size: u64,
 }
 
+/// `SearchResColumn` represents a column of the Microsoft Update Catalog search results table, in
+/// the order the catalog displays them.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub enum SearchResColumn {
+    Title,
+    Product,
+    Classification,
+    LastUpdated,
+    Version,
+    Size,
+}
+
+impl SearchResult {
+    /// `architecture` returns the architecture the title names, e.g. `x64` for a title ending in
+    /// `for x64-based Systems`, or `None` if the title does not name one. Search results do not
+    /// otherwise include the architecture.
+    pub fn architecture(&self) -> Option<String> {
+        parse_architecture_from_title(&self.title)
+    }
+
+    /// `size_human` returns the size formatted the way the catalog displays it, e.g. `160.9 MB`.
+    pub fn size_human(&self) -> String {
+        format_size(self.size)
+    }
+}
+
+/// `format_size` formats a size in bytes with one decimal place and a binary KB, MB, or GB unit,
+/// matching how the catalog displays sizes.
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if size < 1024 {
+        return format!("{} bytes", size);
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
 /// `SearchPageMeta` is an internal state tracker for a SearchResultStream page.
 #[derive(Eq, PartialEq, Debug)]
 pub struct SearchPageMeta {
@@ -45,23 +101,70 @@ pub struct SearchPageMeta {
 }
 
 impl SearchPageMeta {
-    /// `as_map` returns a HashMap of the metadata values, excluding the pagination metadata.
-    pub fn as_map(&self) -> HashMap<&str, &str> {
+    /// `as_map` returns a HashMap of the metadata values keyed by the given form field names,
+    /// excluding the pagination metadata.
+    ///
+    /// # Parameters
+    ///
+    /// * `fields` - The names of the search form fields.
+    pub fn as_map<'a>(&'a self, fields: &'a SearchFormFields) -> HashMap<&'a str, &'a str> {
         let mut map = HashMap::new();
-        map.insert("__EVENTTARGET", self.event_target.as_str());
-        map.insert("__EVENTARGUMENT", self.event_argument.as_str());
-        map.insert("__EVENTVALIDATION", self.event_validation.as_str());
-        map.insert("__VIEWSTATE", self.view_state.as_str());
-        map.insert("__VIEWSTATEGENERATOR", self.view_state_generator.as_str());
+        map.insert(fields.event_target.as_str(), self.event_target.as_str());
+        map.insert(fields.event_argument.as_str(), self.event_argument.as_str());
+        map.insert(fields.event_validation.as_str(), self.event_validation.as_str());
+        map.insert(fields.view_state.as_str(), self.view_state.as_str());
+        map.insert(fields.view_state_generator.as_str(), self.view_state_generator.as_str());
 
         map
     }
 }
 
+/// `SearchFormFields` represents the names of the ASP.NET form fields the catalog search page
+/// posts to request the next page of results. The catalog has renamed its controls before, so
+/// the names can be changed with `ClientBuilder::search_form_fields` without a new release. The
+/// hidden fields are read from the elements with the same ids as their names.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct SearchFormFields {
+    /// `event_target` is the name of the field holding the control that posted the form. The
+    /// default is `__EVENTTARGET`.
+    pub event_target: String,
+    /// `event_argument` defaults to `__EVENTARGUMENT`.
+    pub event_argument: String,
+    /// `event_validation` defaults to `__EVENTVALIDATION`.
+    pub event_validation: String,
+    /// `view_state` defaults to `__VIEWSTATE`.
+    pub view_state: String,
+    /// `view_state_generator` defaults to `__VIEWSTATEGENERATOR`.
+    pub view_state_generator: String,
+    /// `next_page_event_target` is the name of the next page link control, which is posted as the
+    /// event target. The id of the link is the name with `$` replaced by `_`. The default is
+    /// `ctl00$catalogBody$nextPageLinkText`.
+    pub next_page_event_target: String,
+}
+
+impl Default for SearchFormFields {
+    /// `default` creates a `SearchFormFields` with the names the catalog currently uses.
+    fn default() -> Self {
+        SearchFormFields {
+            event_target: "__EVENTTARGET".to_string(),
+            event_argument: "__EVENTARGUMENT".to_string(),
+            event_validation: "__EVENTVALIDATION".to_string(),
+            view_state: "__VIEWSTATE".to_string(),
+            view_state_generator: "__VIEWSTATEGENERATOR".to_string(),
+            next_page_event_target: "ctl00$catalogBody$nextPageLinkText".to_string(),
+        }
+    }
+}
+
 /// `SearchPagePaginationMeta` contains page count information for a SearchResultStream page.
 #[derive(Eq, PartialEq, Debug)]
 pub struct SearchPagePaginationMeta {
+    /// `has_next_page` is true if the catalog links a next page. It is false until the first page
+    /// has been received.
     pub has_next_page: bool,
+    /// `initialized` is true once a page has been received, after which the other values reflect
+    /// the last page received.
+    pub initialized: bool,
     pub too_many_results: bool,
     pub current_page: i16,
     pub page_size: i16,
@@ -70,11 +173,12 @@ pub struct SearchPagePaginationMeta {
 }
 
 impl Default for SearchPagePaginationMeta {
-    /// `default` creates a new SearchPagePaginationMeta with all values set to 0
+    /// `default` creates a new SearchPagePaginationMeta with all values set to 0, before any page
+    /// has been received
     fn default() -> Self {
         SearchPagePaginationMeta {
-            // has_next_page is set to true for the first page
-            has_next_page: true,
+            has_next_page: false,
+            initialized: false,
             too_many_results: false,
             current_page: 0,
             page_size: 0,
@@ -85,8 +189,7 @@ impl Default for SearchPagePaginationMeta {
 }
 
 impl Default for SearchPageMeta {
-    /// `default` creates a new SearchPage with empty values and the `has_next_page` set to true
-    /// for the first page
+    /// `default` creates a new SearchPage with empty values, before any page has been received
     fn default() -> Self {
         SearchPageMeta {
             event_target: "".to_string(),
@@ -102,51 +205,878 @@ impl Default for SearchPageMeta {
 /// `Update` represents the details of a single update from the Microsoft Update Catalog.
 #[derive(Eq, PartialEq, Debug)]
 pub struct Update {
+    /// `title` is the title shown on the update details page. Prefer it for display, as it is the
+    /// canonical title of the update.
     pub title: String,
-    pub id: String,
+    /// `search_title` is the title of the search result the update was retrieved for, if it
+    /// differs from `title`. The search results sometimes omit suffixes the details page includes,
+    /// such as `UUP`, or lag behind a title change on the details page. It is only set by
+    /// `Client::get_update_for` and `Client::search_with_details`.
+    pub search_title: Option<String>,
+    pub id: UpdateId,
     pub kb: String,
+    /// `kb_numbers` contains every KB number referenced in the title and description, starting
+    /// with the primary `kb`. Combined packages, such as a servicing stack update bundled with a
+    /// cumulative update, reference more than one.
+    pub kb_numbers: Vec<String>,
     pub classification: String,
     pub last_modified: chrono::NaiveDate,
+    /// `last_modified_raw` is the last updated date exactly as the catalog returned it.
+    pub last_modified_raw: String,
+    /// `size` is the size in bytes, or 0 if the catalog does not list a size, as it does for some
+    /// metadata-only updates. `validate` reports a size of 0 as a problem.
     pub size: u64,
     pub description: String,
+    /// `description_links` are the links embedded in the description, in the order they appear.
+    pub description_links: Vec<Url>,
+    /// `architecture` is the architecture listed on the details page. If the catalog lists none,
+    /// it is the architecture the title names, e.g. `x64` for a title ending in
+    /// `for x64-based Systems`, if any.
     pub architecture: Option<String>,
     pub supported_products: Vec<String>,
+    /// `operating_systems` are the operating systems the update targets, which the catalog lists
+    /// separately from the supported products for some updates, e.g. `Windows 11` for an update
+    /// to `Office`. It is empty if the catalog does not list them separately.
+    pub operating_systems: Vec<String>,
     pub supported_languages: Vec<String>,
     pub msrc_number: Option<String>,
     pub msrc_severity: Option<String>,
+    /// `kb_article_url` is the link of the KB article number, if the catalog links it.
+    pub kb_article_url: Option<Url>,
+    /// `security_bulletin_url` is the link of the MSRC number, if the catalog links it.
+    pub security_bulletin_url: Option<Url>,
+    /// `info_url` is the first of the more information links.
     pub info_url: Url,
+    /// `more_info_urls` are all of the more information links, in the order they appear.
+    pub more_info_urls: Vec<Url>,
+    /// `support_url` is the first of the support links.
     pub support_url: Url,
+    /// `support_urls` are all of the support links, in the order they appear.
+    pub support_urls: Vec<Url>,
+    /// `reboot_behavior` is the primary reboot behavior of the update, the first of
+    /// `reboot_behaviors`.
     pub reboot_behavior: RebootBehavior,
+    /// `reboot_behaviors` contains every reboot behavior listed for the update. The catalog
+    /// lists more than one for some updates, e.g. `Can request restart / Recommended`.
+    pub reboot_behaviors: Vec<RebootBehavior>,
     pub requires_user_input: bool,
     pub is_exclusive_install: bool,
     pub requires_network_connectivity: bool,
     pub uninstall_notes: Option<String>,
     pub uninstall_steps: Option<String>,
+    /// `driver_info` is the driver details of a driver update, or `None` for other updates.
+    pub driver_info: Option<DriverInfo>,
     pub supersedes: Vec<SupersedesUpdate>,
+    /// `supersedes_truncated` is true if the catalog only listed part of the updates this update
+    /// replaces, in which case `supersedes` is incomplete.
+    pub supersedes_truncated: bool,
     pub superseded_by: Vec<SupersededByUpdate>,
+    /// `superseded_by_truncated` is true if the catalog only listed part of the updates that
+    /// replace this update, in which case `superseded_by` is incomplete.
+    pub superseded_by_truncated: bool,
 }
 
-/// `SupersededByUpdate` represents an update that supersedes the current update.
+/// `UpdateFields` selects the sections of the update details page `Client::get_update_fields`
+/// parses. Selections are combined with `|`.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct UpdateFields(u8);
+
+impl UpdateFields {
+    pub const TITLE: UpdateFields = UpdateFields(1);
+    pub const KB: UpdateFields = UpdateFields(1 << 1);
+    pub const CLASSIFICATION: UpdateFields = UpdateFields(1 << 2);
+    pub const LAST_MODIFIED: UpdateFields = UpdateFields(1 << 3);
+    pub const SIZE: UpdateFields = UpdateFields(1 << 4);
+    pub const SUPERSEDES: UpdateFields = UpdateFields(1 << 5);
+    pub const SUPERSEDED_BY: UpdateFields = UpdateFields(1 << 6);
+    pub const ALL: UpdateFields = UpdateFields((1 << 7) - 1);
+
+    /// `contains` returns true if all of the given fields are selected.
+    pub fn contains(&self, fields: UpdateFields) -> bool {
+        self.0 & fields.0 == fields.0
+    }
+}
+
+impl std::ops::BitOr for UpdateFields {
+    type Output = UpdateFields;
+
+    fn bitor(self, rhs: UpdateFields) -> UpdateFields {
+        UpdateFields(self.0 | rhs.0)
+    }
+}
+
+/// `PartialUpdate` represents the sections of an update selected with `UpdateFields`. Fields that
+/// were not selected are `None`.
+#[derive(Eq, PartialEq, Debug, Default)]
+pub struct PartialUpdate {
+    pub title: Option<String>,
+    pub kb: Option<String>,
+    pub classification: Option<String>,
+    pub last_modified: Option<chrono::NaiveDate>,
+    pub size: Option<u64>,
+    pub supersedes: Option<Vec<SupersedesUpdate>>,
+    pub supersedes_truncated: Option<bool>,
+    pub superseded_by: Option<Vec<SupersededByUpdate>>,
+    pub superseded_by_truncated: Option<bool>,
+}
+
+/// `UpdateSummary` represents the identifying details of a single update from the Microsoft
+/// Update Catalog. It is a lightweight alternative to `Update` that skips the supersedence and
+/// URL sections of the details page.
 #[derive(Eq, PartialEq, Debug)]
-pub struct SupersededByUpdate {
+pub struct UpdateSummary {
     pub title: String,
+    pub id: UpdateId,
     pub kb: String,
-    pub id: String,
+    pub classification: String,
+    pub last_modified: chrono::NaiveDate,
+    pub size: u64,
+}
+
+/// `OFFICIAL_URL_DOMAINS` are the domains that an update's information and support links are
+/// expected to point to. Subdomains, such as `support.microsoft.com`, are included.
+const OFFICIAL_URL_DOMAINS: [&str; 2] = ["microsoft.com", "windowsupdate.com"];
+
+// is_official_host returns true if the host is one of the official domains or a subdomain of one.
+fn is_official_host(host: Option<&str>) -> bool {
+    let host = match host {
+        Some(h) => h.to_ascii_lowercase(),
+        None => return false,
+    };
+    OFFICIAL_URL_DOMAINS
+        .iter()
+        .any(|d| host == *d || host.ends_with(&format!(".{}", d)))
+}
+
+impl Update {
+    /// `validate` checks the update for states that suggest the details page was not parsed
+    /// correctly. It returns a description of each problem found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = vec![];
+        if self.title.is_empty() {
+            problems.push("title is empty".to_string());
+        }
+        if self.kb.is_empty() || !self.kb.chars().all(|c| c.is_ascii_digit()) {
+            problems.push(format!("kb '{}' is not a KB number", self.kb));
+        }
+        if self.size == 0 {
+            problems.push("size is zero or not listed".to_string());
+        }
+        for (name, url) in [("info_url", &self.info_url), ("support_url", &self.support_url)] {
+            if !is_official_host(url.host_str()) {
+                problems.push(format!("{} '{}' is not an official Microsoft url", name, url));
+            }
+        }
+        if self.last_modified > chrono::Utc::now().date_naive() {
+            problems.push(format!("last_modified '{}' is in the future", self.last_modified));
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(problems),
+        }
+    }
+
+    /// `size_human` returns the size formatted the way the catalog displays it, e.g. `316.2 MB`.
+    pub fn size_human(&self) -> String {
+        format_size(self.size)
+    }
+
+    /// `applies_to_product` returns true if the product is one of the update's supported
+    /// products. The comparison is case-insensitive and ignores differences in whitespace.
+    ///
+    /// # Parameters
+    ///
+    /// * `product` - The product name to check, e.g. `Windows 11`.
+    pub fn applies_to_product(&self, product: &str) -> bool {
+        let product = normalize_product(product);
+        self.supported_products
+            .iter()
+            .any(|p| normalize_product(p) == product)
+    }
+
+    /// `related_update_ids` returns the ids of every update this update references. The catalog
+    /// only links the updates that supersede this one, as the updates it supersedes are listed
+    /// without ids, so these are the `superseded_by` ids.
+    pub fn related_update_ids(&self) -> Vec<&str> {
+        self.superseded_by.iter().map(|u| u.id.as_str()).collect()
+    }
+
+    /// `info_url_host` returns the host of the update's information url.
+    pub fn info_url_host(&self) -> Option<&str> {
+        self.info_url.host_str()
+    }
+
+    /// `support_url_host` returns the host of the update's support url.
+    pub fn support_url_host(&self) -> Option<&str> {
+        self.support_url.host_str()
+    }
+
+    /// `links_look_official` returns true if both the information and support urls point to a
+    /// known Microsoft domain, such as `support.microsoft.com` or `technet.microsoft.com`.
+    pub fn links_look_official(&self) -> bool {
+        is_official_host(self.info_url_host()) && is_official_host(self.support_url_host())
+    }
+
+    /// `diff` compares the update against another snapshot of the same update and returns the
+    /// fields that differ along with any supersedence entries that are new in `other`.
+    pub fn diff(&self, other: &Update) -> UpdateDiff {
+        let mut fields = vec![];
+        let mut changed = |field: UpdateField, differs: bool| {
+            if differs {
+                fields.push(field);
+            }
+        };
+        changed(UpdateField::Title, self.title != other.title);
+        changed(UpdateField::SearchTitle, self.search_title != other.search_title);
+        changed(UpdateField::Id, self.id != other.id);
+        changed(UpdateField::Kb, self.kb != other.kb);
+        changed(UpdateField::KbNumbers, self.kb_numbers != other.kb_numbers);
+        changed(UpdateField::Classification, self.classification != other.classification);
+        changed(UpdateField::LastModified, self.last_modified != other.last_modified);
+        changed(UpdateField::Size, self.size != other.size);
+        changed(UpdateField::Description, self.description != other.description);
+        changed(UpdateField::DescriptionLinks, self.description_links != other.description_links);
+        changed(UpdateField::Architecture, self.architecture != other.architecture);
+        changed(UpdateField::SupportedProducts, self.supported_products != other.supported_products);
+        changed(UpdateField::OperatingSystems, self.operating_systems != other.operating_systems);
+        changed(UpdateField::SupportedLanguages, self.supported_languages != other.supported_languages);
+        changed(UpdateField::MsrcNumber, self.msrc_number != other.msrc_number);
+        changed(UpdateField::MsrcSeverity, self.msrc_severity != other.msrc_severity);
+        changed(UpdateField::KbArticleUrl, self.kb_article_url != other.kb_article_url);
+        changed(
+            UpdateField::SecurityBulletinUrl,
+            self.security_bulletin_url != other.security_bulletin_url,
+        );
+        changed(UpdateField::InfoUrl, self.info_url != other.info_url);
+        changed(UpdateField::MoreInfoUrls, self.more_info_urls != other.more_info_urls);
+        changed(UpdateField::SupportUrl, self.support_url != other.support_url);
+        changed(UpdateField::SupportUrls, self.support_urls != other.support_urls);
+        changed(UpdateField::RebootBehavior, self.reboot_behavior != other.reboot_behavior);
+        changed(UpdateField::RebootBehaviors, self.reboot_behaviors != other.reboot_behaviors);
+        changed(UpdateField::RequiresUserInput, self.requires_user_input != other.requires_user_input);
+        changed(UpdateField::IsExclusiveInstall, self.is_exclusive_install != other.is_exclusive_install);
+        changed(
+            UpdateField::RequiresNetworkConnectivity,
+            self.requires_network_connectivity != other.requires_network_connectivity,
+        );
+        changed(UpdateField::UninstallNotes, self.uninstall_notes != other.uninstall_notes);
+        changed(UpdateField::UninstallSteps, self.uninstall_steps != other.uninstall_steps);
+        changed(UpdateField::DriverInfo, self.driver_info != other.driver_info);
+        changed(UpdateField::Supersedes, self.supersedes != other.supersedes);
+        changed(UpdateField::SupersedesTruncated, self.supersedes_truncated != other.supersedes_truncated);
+        changed(UpdateField::SupersededBy, self.superseded_by != other.superseded_by);
+        changed(
+            UpdateField::SupersededByTruncated,
+            self.superseded_by_truncated != other.superseded_by_truncated,
+        );
+
+        UpdateDiff {
+            fields,
+            new_supersedes: other
+                .supersedes
+                .iter()
+                .filter(|u| !self.supersedes.contains(u))
+                .cloned()
+                .collect(),
+            new_superseded_by: other
+                .superseded_by
+                .iter()
+                .filter(|u| !self.superseded_by.contains(u))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// `UpdateField` identifies a single field of an `Update`.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum UpdateField {
+    Title,
+    SearchTitle,
+    Id,
+    Kb,
+    KbNumbers,
+    Classification,
+    LastModified,
+    Size,
+    Description,
+    DescriptionLinks,
+    Architecture,
+    SupportedProducts,
+    OperatingSystems,
+    SupportedLanguages,
+    MsrcNumber,
+    MsrcSeverity,
+    KbArticleUrl,
+    SecurityBulletinUrl,
+    InfoUrl,
+    MoreInfoUrls,
+    SupportUrl,
+    SupportUrls,
+    RebootBehavior,
+    RebootBehaviors,
+    RequiresUserInput,
+    IsExclusiveInstall,
+    RequiresNetworkConnectivity,
+    UninstallNotes,
+    UninstallSteps,
+    DriverInfo,
+    Supersedes,
+    SupersedesTruncated,
+    SupersededBy,
+    SupersededByTruncated,
+}
+
+/// `UpdateDiff` represents the field-level changes between two snapshots of an `Update`.
+#[derive(Eq, PartialEq, Debug, Default)]
+pub struct UpdateDiff {
+    pub fields: Vec<UpdateField>,
+    pub new_supersedes: Vec<SupersedesUpdate>,
+    pub new_superseded_by: Vec<SupersededByUpdate>,
+}
+
+impl UpdateDiff {
+    /// `is_empty` returns true if the two snapshots are identical.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// `SearchDiff` represents the changes between two collections of search results for the same
+/// query, matched by update id.
+#[derive(Eq, PartialEq, Debug, Default)]
+pub struct SearchDiff {
+    /// `added` are the results only in the new collection, in the order they appear there.
+    pub added: Vec<SearchResult>,
+    /// `removed` are the results only in the old collection, in the order they appear there.
+    pub removed: Vec<SearchResult>,
+    /// `changed` are the `(old, new)` pairs of results with the same id whose content differs, in
+    /// the order they appear in the new collection.
+    pub changed: Vec<(SearchResult, SearchResult)>,
+}
+
+impl SearchDiff {
+    /// `is_empty` returns true if the two collections contain the same results.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// `diff_search_results` compares two collections of search results, such as the results of the
+/// same query run on different days. Results are matched by id, and if an id appears more than
+/// once in a collection only its first occurrence is compared.
+///
+/// # Parameters
+///
+/// * `old` - The earlier search results.
+/// * `new` - The later search results.
+pub fn diff_search_results(old: &[SearchResult], new: &[SearchResult]) -> SearchDiff {
+    let mut old_by_id: HashMap<&UpdateId, &SearchResult> = HashMap::new();
+    for r in old {
+        old_by_id.entry(&r.id).or_insert(r);
+    }
+    let mut new_by_id: HashMap<&UpdateId, &SearchResult> = HashMap::new();
+    for r in new {
+        new_by_id.entry(&r.id).or_insert(r);
+    }
+
+    let mut diff = SearchDiff::default();
+    for r in new {
+        if new_by_id.get(&r.id).is_some_and(|first| !std::ptr::eq(*first, r)) {
+            continue;
+        }
+        match old_by_id.get(&r.id) {
+            None => diff.added.push(r.clone()),
+            Some(o) if *o != r => diff.changed.push(((*o).clone(), r.clone())),
+            Some(_) => {}
+        }
+    }
+    for r in old {
+        if old_by_id.get(&r.id).is_some_and(|first| std::ptr::eq(*first, r)) && !new_by_id.contains_key(&r.id) {
+            diff.removed.push(r.clone());
+        }
+    }
+    diff
+}
+
+/// `compare_kb` compares two KB numbers numerically, so `KB958644` sorts before `KB5030211`. The
+/// `KB` prefix is optional and case-insensitive. KB numbers that can't be parsed sort after the
+/// others, ordered by their text.
+///
+/// # Parameters
+///
+/// * `a` - The first KB number, e.g. `KB5030211` or `5030211`.
+/// * `b` - The second KB number.
+pub fn compare_kb(a: &str, b: &str) -> std::cmp::Ordering {
+    match (parse_kb_number(a), parse_kb_number(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+// parse_kb_number parses the numeric portion of a KB number, ignoring the optional `KB` prefix.
+fn parse_kb_number(kb: &str) -> Option<u64> {
+    let kb = kb.trim();
+    let kb = match kb.get(..2) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("kb") => &kb[2..],
+        _ => kb,
+    };
+    kb.parse().ok()
+}
+
+/// `export_wsus_csv` writes the updates as CSV in the layout WSUS tooling imports, with a header
+/// row and one record per update. The columns are, in order:
+///
+/// * `KBArticleID` - The KB number, without the `KB` prefix.
+/// * `Title` - The title.
+/// * `Classification` - The classification, e.g. `Security Updates`.
+/// * `Product` - The supported products, separated by `; `.
+/// * `Size` - The size in bytes.
+/// * `UpdateID` - The update id.
+///
+/// It returns the number of records written.
+///
+/// # Parameters
+///
+/// * `updates` - The updates to export.
+/// * `w` - The writer to write the CSV to.
+#[cfg(feature = "csv")]
+pub fn export_wsus_csv(updates: &[Update], w: impl std::io::Write) -> Result<usize, Error> {
+    let mut writer = csv::Writer::from_writer(w);
+    writer
+        .write_record(["KBArticleID", "Title", "Classification", "Product", "Size", "UpdateID"])
+        .map_err(|e| Error::Export(e.to_string()))?;
+    for u in updates {
+        writer
+            .write_record([
+                u.kb.as_str(),
+                u.title.as_str(),
+                u.classification.as_str(),
+                u.supported_products.join("; ").as_str(),
+                u.size.to_string().as_str(),
+                u.id.as_str(),
+            ])
+            .map_err(|e| Error::Export(e.to_string()))?;
+    }
+    writer.flush().map_err(|e| Error::Export(e.to_string()))?;
+    Ok(updates.len())
+}
+
+/// `RequestStats` represents the attempts made to complete a request to the catalog.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Default)]
+pub struct RequestStats {
+    /// `attempts` is the number of times the request was sent, including the retries of
+    /// throttled attempts. It is 0 for a response replayed from a cassette.
+    pub attempts: u32,
+    /// `total_wait` is the total time spent waiting before retrying throttled attempts.
+    pub total_wait: Duration,
+}
+
+/// `Timing` represents where the time was spent retrieving an update.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Timing {
+    /// `request` is the time spent requesting the page and reading the response.
+    pub request: Duration,
+    /// `parse` is the time spent parsing the page.
+    pub parse: Duration,
+    /// `total` is the time spent retrieving the update, including the request and parsing.
+    pub total: Duration,
+}
+
+/// `UpdateId` represents the id of an update in the Microsoft Update Catalog, a GUID such as
+/// `9397a21f-246c-453b-ac05-65bf4fc6b68b`. It is not the KB number of the update. The `Client`
+/// methods that take an update id take an `UpdateId`, so a KB number is rejected when it is parsed
+/// rather than sent to the catalog.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Clone)]
+pub struct UpdateId(String);
+
+impl UpdateId {
+    /// `as_str` returns the update id as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for UpdateId {
+    type Err = Error;
+
+    /// `from_str` parses an update id, returning an error if it is not a GUID.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let is_guid = s.len() == 36
+            && s.char_indices().all(|(i, c)| match i {
+                8 | 13 | 18 | 23 => c == '-',
+                _ => c.is_ascii_hexdigit(),
+            });
+        match is_guid {
+            true => Ok(UpdateId(s.to_string())),
+            false => Err(Error::Parsing(format!("'{}' is not a valid update id", s))),
+        }
+    }
+}
+
+impl TryFrom<&str> for UpdateId {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Deref for UpdateId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for UpdateId {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq<&str> for UpdateId {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<UpdateId> for &str {
+    fn eq(&self, other: &UpdateId) -> bool {
+        *self == other.0
+    }
+}
+
+impl std::fmt::Display for UpdateId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `SearchQuery` represents a query for the Microsoft Update Catalog search.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct SearchQuery(String);
+
+impl SearchQuery {
+    /// `SEARCH_OPERATORS` are the characters the catalog search treats as operators rather than
+    /// as part of the words searched for.
+    const SEARCH_OPERATORS: [char; 4] = ['"', '(', ')', '*'];
+
+    /// `new` creates a query that is passed to the catalog as is, so search operators in it take
+    /// effect.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The search query to use.
+    pub fn new(query: &str) -> Self {
+        SearchQuery(query.to_string())
+    }
+
+    /// `escaped` creates a query that matches the given text literally, for example an update
+    /// title such as `Update (KB123)`. The characters the catalog search treats as operators,
+    /// double quotes, parentheses and asterisks, are replaced with spaces, which the catalog
+    /// ignores between words.
+    ///
+    /// # Parameters
+    ///
+    /// * `query` - The literal text to search for.
+    pub fn escaped(query: &str) -> Self {
+        let words = query
+            .replace(Self::SEARCH_OPERATORS, " ")
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        SearchQuery(words)
+    }
+
+    /// `as_str` returns the query to pass to `Client::search`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SearchQuery {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl std::fmt::Display for SearchQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `ResponseInfo` describes a response the client received from the catalog.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct ResponseInfo {
+    pub method: String,
+    /// `url` is the url of the response, after any redirects.
+    pub url: Url,
+    pub status: u16,
+    /// `body_len` is the length of the decoded body for responses whose body is read. For
+    /// responses whose body is not read, such as retried requests and redirect resolution, it is
+    /// the `Content-Length` header, which is not sent for compressed or chunked responses.
+    pub body_len: Option<u64>,
+}
+
+/// `ResolvedDownload` represents the final location of a download after following redirects.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct ResolvedDownload {
+    pub url: Url,
+    pub content_length: Option<u64>,
+}
+
+impl ResolvedDownload {
+    /// `verify_file_size` checks whether the file at the given path has the size reported by the
+    /// server for the download, catching truncated downloads when no digest is available.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - The path of the downloaded file.
+    pub fn verify_file_size(&self, path: impl AsRef<Path>) -> Result<bool, Error> {
+        let expected = self.content_length.ok_or(Error::Internal(format!(
+            "No content length was reported for {}",
+            self.url
+        )))?;
+        let metadata = std::fs::metadata(path.as_ref()).map_err(|e| {
+            Error::Internal(format!("Failed to read {}: {}", path.as_ref().display(), e))
+        })?;
+        Ok(metadata.len() == expected)
+    }
+}
+
+/// `DriverInfo` represents the driver details listed for a driver update.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct DriverInfo {
+    pub version: String,
+    pub date: chrono::NaiveDate,
+    pub provider: String,
+    pub class: String,
+}
+
+/// `SupersededByUpdate` represents an update that supersedes the current update.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct SupersededByUpdate {
+    pub title: String,
+    /// `kb` is the KB number from the title, or `None` if the title does not include one.
+    pub kb: Option<String>,
+    pub id: UpdateId,
+    /// `release_month` is the first day of the release month the title begins with, e.g.
+    /// 2023-09-01 for `2023-09 Cumulative Update for ...`. The catalog does not list a date next to
+    /// superseding updates, so this is only a month approximation taken from the title for
+    /// ordering them, and is `None` for titles without a release month.
+    pub release_month: Option<chrono::NaiveDate>,
 }
 
 /// `SupersedesUpdate` represents an update that the current update supersedes.
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub struct SupersedesUpdate {
     pub title: String,
-    pub kb: String,
+    /// `kb` is the KB number from the title, or `None` if the title does not include one.
+    pub kb: Option<String>,
 }
 
 /// `RebootBehavior` represents the reboot behavior of an update.
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
 pub enum RebootBehavior {
     Required,
     CanRequest,
     Recommended,
     NotRequired,
     NeverRestarts,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{parse_search_results_only, parse_update_details};
+
+    fn load_update() -> Update {
+        let html = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resources/test/msuc_update_details.html"
+        ))
+        .expect("Failed to load test data");
+        parse_update_details(&html).expect("Failed to parse test data")
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_export_wsus_csv() {
+        let mut update = load_update();
+        update.supported_products.push("Windows 10".to_string());
+        let mut out = vec![];
+        let count = export_wsus_csv(&[update], &mut out).expect("Failed to export updates");
+        let csv = String::from_utf8(out).expect("Expected CSV to be valid UTF-8");
+        let mut lines = csv.lines();
+
+        assert_eq!(1, count);
+        assert_eq!(Some("KBArticleID,Title,Classification,Product,Size,UpdateID"), lines.next());
+        assert_eq!(
+            Some("5025305,2023-04 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5025305),Updates,Windows 11; Windows 10,331559731,1b0b70c0-191e-42f6-8808-c1b50deacb3b"),
+            lines.next()
+        );
+        assert_eq!(None, lines.next());
+    }
+
+    #[test]
+    fn test_update_validate() {
+        assert_eq!(Ok(()), load_update().validate());
+
+        let mut update = load_update();
+        update.title = "".to_string();
+        update.kb = "KB5025305".to_string();
+        update.size = 0;
+        update.info_url = Url::parse("https://example.com/help/5025305").expect("Failed to parse URL for test data");
+        update.last_modified = chrono::NaiveDate::MAX;
+        let problems = update.validate().expect_err("Expected validation to fail");
+        assert_eq!(5, problems.len());
+        assert!(problems[3].starts_with("info_url"));
+    }
+
+    #[test]
+    fn test_update_links_look_official() {
+        let mut update = load_update();
+        assert_eq!(Some("support.microsoft.com"), update.info_url_host());
+        assert_eq!(Some("support.microsoft.com"), update.support_url_host());
+        assert!(update.links_look_official());
+
+        update.support_url = Url::parse("https://notmicrosoft.com/fp179701").expect("Failed to parse URL for test data");
+        assert!(!update.links_look_official());
+    }
+
+    #[test]
+    fn test_update_applies_to_product() {
+        let mut update = load_update();
+        update.supported_products.push("Windows 10,  version 1903 and later".to_string());
+        assert!(update.applies_to_product("Windows 11"));
+        assert!(update.applies_to_product(" windows  11 "));
+        assert!(update.applies_to_product("Windows 10, version 1903 and later"));
+        assert!(!update.applies_to_product("Windows 10"));
+    }
+
+    #[test]
+    fn test_format_size() {
+        let test_cases = [
+            (0, "0 bytes"),
+            (1023, "1023 bytes"),
+            (1536, "1.5 KB"),
+            (168715878, "160.9 MB"),
+            (5368709120, "5.0 GB"),
+            (2199023255552, "2048.0 GB"),
+        ];
+        for tc in test_cases.iter() {
+            assert_eq!(tc.1, format_size(tc.0));
+        }
+        assert_eq!("316.2 MB", load_update().size_human());
+    }
+
+    #[test]
+    fn test_update_diff() {
+        let old = load_update();
+        assert!(old.diff(&load_update()).is_empty());
+
+        let mut new = load_update();
+        new.size += 1;
+        new.reboot_behavior = RebootBehavior::Required;
+        new.superseded_by.push(SupersededByUpdate {
+            title: "2023-10 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5031354)".to_string(),
+            kb: Some("5031354".to_string()),
+            id: "00000000-0000-0000-0000-000000000000".parse().expect("Failed to parse update id"),
+            release_month: chrono::NaiveDate::from_ymd_opt(2023, 10, 1),
+        });
+        let diff = old.diff(&new);
+        assert_eq!(
+            vec![UpdateField::Size, UpdateField::RebootBehavior, UpdateField::SupersededBy],
+            diff.fields
+        );
+        assert!(diff.new_supersedes.is_empty());
+        assert_eq!(1, diff.new_superseded_by.len());
+        assert_eq!(Some("5031354".to_string()), diff.new_superseded_by[0].kb);
+    }
+
+    #[test]
+    fn test_update_related_update_ids() {
+        let update = load_update();
+        assert_eq!(update.superseded_by.len(), update.related_update_ids().len());
+        assert_eq!(update.superseded_by[0].id, update.related_update_ids()[0]);
+    }
+
+    #[test]
+    fn test_compare_kb() {
+        use std::cmp::Ordering;
+        assert_eq!(Ordering::Less, compare_kb("KB958644", "KB5030211"));
+        assert_eq!(Ordering::Equal, compare_kb("kb5030211", "5030211"));
+        assert_eq!(Ordering::Greater, compare_kb("Kb5030211", "KB958644"));
+        assert_eq!(Ordering::Less, compare_kb("5030211", "not a kb"));
+        assert_eq!(Ordering::Greater, compare_kb("", "KB958644"));
+
+        let mut kbs = vec!["KB5030211", "invalid", "KB958644", "kb5025305"];
+        kbs.sort_by(|a, b| compare_kb(a, b));
+        assert_eq!(vec!["KB958644", "kb5025305", "KB5030211", "invalid"], kbs);
+    }
+
+    #[test]
+    fn test_diff_search_results() {
+        let html = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resources/test/msuc_small_result.html"
+        ))
+        .expect("Failed to load test data");
+        let old = parse_search_results_only(&html).expect("Failed to parse test data");
+        assert!(diff_search_results(&old, &old).is_empty());
+
+        let mut new = old[1..].to_vec();
+        new[0].size += 1;
+        let mut added = old[0].clone();
+        added.id = "00000000-0000-0000-0000-000000000000".parse().expect("Failed to parse update id");
+        new.push(added.clone());
+        new.push(added.clone());
+        let diff = diff_search_results(&old, &new);
+        assert_eq!(vec![added], diff.added);
+        assert_eq!(vec![old[0].clone()], diff.removed);
+        assert_eq!(vec![(old[1].clone(), new[0].clone())], diff.changed);
+    }
+
+    #[test]
+    fn test_resolved_download_verify_file_size() {
+        let path = std::env::temp_dir().join(format!("msuc-verify-{}.msu", std::process::id()));
+        std::fs::write(&path, [0u8; 16]).expect("Failed to write test file");
+        let url = Url::parse("https://catalog.s.download.windowsupdate.com/test.msu").expect("Failed to parse URL");
+        let download = |content_length| ResolvedDownload { url: url.clone(), content_length };
+        assert!(download(Some(16)).verify_file_size(&path).expect("Failed to verify file size"));
+        assert!(!download(Some(32)).verify_file_size(&path).expect("Failed to verify file size"));
+        assert!(download(None).verify_file_size(&path).is_err());
+        std::fs::remove_file(&path).expect("Failed to remove test file");
+        assert!(download(Some(16)).verify_file_size(&path).is_err());
+    }
+
+    #[test]
+    fn test_search_query_escaped() {
+        let title = "2023-09 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5030219)";
+        assert_eq!(title, SearchQuery::new(title).as_str());
+        assert_eq!(
+            "2023-09 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems KB5030219",
+            SearchQuery::escaped(title).as_str()
+        );
+        assert_eq!("Update KB123", SearchQuery::escaped("\"Update (KB123)\"").as_str());
+        assert_eq!("Security Update", SearchQuery::escaped("Security* Update").to_string());
+    }
+
+    #[test]
+    fn test_update_id() {
+        let id: UpdateId = "9397a21f-246c-453b-ac05-65bf4fc6b68b".parse().expect("Failed to parse update id");
+        assert_eq!("9397a21f-246c-453b-ac05-65bf4fc6b68b", id.as_str());
+        assert!(UpdateId::try_from(" 9397A21F-246C-453B-AC05-65BF4FC6B68B ").is_ok());
+        assert!("958644".parse::<UpdateId>().is_err());
+        assert!("KB958644".parse::<UpdateId>().is_err());
+        assert!("9397a21f-246c-453b-ac05-65bf4fc6b68".parse::<UpdateId>().is_err());
+        assert!("9397a21f+246c-453b-ac05-65bf4fc6b68b".parse::<UpdateId>().is_err());
+    }
+}
@@ -1,22 +1,173 @@
 use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
 use scraper::{Html, Selector};
 use url::Url;
-use crate::model::{Error, RebootBehavior, SearchPageMeta, SearchResult, SupersededByUpdate, SupersedesUpdate, Update, SearchPage, SearchPagePaginationMeta};
+use crate::model::{DriverInfo, Error, SearchFormFields, PartialUpdate, RebootBehavior, UpdateFields, SearchPageMeta, SearchResult, SupersededByUpdate, SupersedesUpdate, Update, SearchPage, SearchPagePaginationMeta, UpdateSummary, SearchResColumn};
 
-#[derive(Eq, PartialEq, Debug)]
-enum SearchResColumn {
-    Title,
-    Product,
-    Classification,
-    LastUpdated,
-    Version,
-    Size,
+/// `SEARCH_PAGE_SIZE` is the fixed number of results the Microsoft Update Catalog returns per
+/// search page. The catalog does not expose a form field or query parameter to change it.
+const SEARCH_PAGE_SIZE: i16 = 25;
+
+/// `CATALOG_BASE_URL` is the url relative links on catalog pages are resolved against.
+const CATALOG_BASE_URL: &str = "https://www.catalog.update.microsoft.com/";
+
+// The candidate selectors for fields of the update details page, in order of preference. The
+// fallbacks select the field within its container, in case the catalog changes the id of the
+// field itself.
+const TITLE_PATHS: &[&str] = &["#ScopedViewHandler_titleText", "#titleDiv"];
+const DATE_PATHS: &[&str] = &["#ScopedViewHandler_date", "#dateDiv span:not(.labelTitle)"];
+const SIZE_PATHS: &[&str] = &["#ScopedViewHandler_size", "#sizeDiv span:not(.labelTitle)"];
+const UPDATE_ID_PATHS: &[&str] = &["#ScopedViewHandler_UpdateID", "#updateIdDiv span:not(.labelTitle)"];
+// There is a typo in the ID of the support url element 'suportUrlDiv', which is also matched
+// without the typo in case the catalog corrects it
+const SUPPORT_URL_PATHS: &[&str] = &["#suportUrlDiv a", "#supportUrlDiv a"];
+
+/// `parse_search_results` parses a page of search results. It returns `None` only if the catalog
+/// reports the search has no results, and an error if the page is not a search results page or
+/// its results table has no rows that could be parsed.
+/// The metadata needed to request the next page is read from the given form fields.
+pub fn parse_search_results(html: &str, fields: &SearchFormFields) -> Result<Option<SearchPage>, Error> {
+    let document = Html::parse_document(html);
+    parse_hidden_error_page(html)?;
+
+    let results = parse_search_rows(&document)?;
+    if results.is_empty() {
+        // only pages where the catalog confirms there are no results are treated as the end of
+        // the results, an empty results table means the rows were not recognized
+        if select_with_path(&document, "#ctl00_catalogBody_noResultText").is_ok() {
+            return Ok(None);
+        }
+        return match select_with_path(&document, "div#tableContainer").is_ok() {
+            true => Err(Error::Parsing("Failed to find any rows in the search results table".to_string())),
+            false => Err(Error::Parsing(
+                "Failed to find search results or the no results message".to_string(),
+            )),
+        };
+    }
+
+    Ok(Some((parse_search_meta(&document, fields)?, results)))
+}
+
+/// `parse_search_page_meta` parses only the metadata needed to request the next page from a
+/// search results page. It can succeed for pages where the results themselves fail to parse.
+pub fn parse_search_page_meta(html: &str, fields: &SearchFormFields) -> Result<SearchPageMeta, Error> {
+    let document = Html::parse_document(html);
+    parse_hidden_error_page(html)?;
+    parse_search_meta(&document, fields)
+}
+
+fn parse_search_meta(document: &Html, fields: &SearchFormFields) -> Result<SearchPageMeta, Error> {
+    let field_value = |name: &str| get_element_attr(document, &format!("#{}", name), "value");
+    Ok(SearchPageMeta {
+        // this can always be the next page, if there aren't more results we just won't
+        // make another request
+        event_target: fields.next_page_event_target.clone(),
+        event_argument: field_value(&fields.event_argument).unwrap_or_else(|_| "".to_string()),
+        event_validation: field_value(&fields.event_validation).unwrap_or_else(|_| "".to_string()),
+        view_state: field_value(&fields.view_state)?,
+        view_state_generator: field_value(&fields.view_state_generator).unwrap_or_else(|_| "".to_string()),
+        // If this element exists, there is a next page
+        pagination: parse_page_count_metadata(document, fields)?,
+    })
 }
 
-pub fn parse_search_results(html: &str) -> Result<Option<SearchPage>, Error> {
+/// `parse_search_results_only` parses the search results from a search results page, such as a
+/// saved copy of one, without the view state and pagination metadata needed to request the next
+/// page. An empty `Vec` is returned if the page has no results.
+///
+/// # Parameters
+///
+/// * `html` - The HTML of the search results page.
+pub fn parse_search_results_only(html: &str) -> Result<Vec<SearchResult>, Error> {
     let document = Html::parse_document(html);
     parse_hidden_error_page(html)?;
+    parse_search_rows(&document)
+}
+
+/// `parse_search_files` reads and parses the search results from each of the given saved search
+/// results pages, such as an archive of pages saved with `SearchResultsStream::next_with_html`.
+/// The files are split across the available threads, and each result is paired with the path it
+/// came from in the order given. A file that cannot be read or parsed does not stop the others.
+///
+/// # Parameters
+///
+/// * `paths` - The paths of the saved search results pages.
+pub fn parse_search_files(paths: &[PathBuf]) -> Vec<(PathBuf, Result<Vec<SearchResult>, Error>)> {
+    // scoped std threads are used rather than a thread pool crate, since the files are split
+    // into one chunk per thread up front and the threads only live for the duration of the call
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = paths.len().div_ceil(threads).max(1);
+    std::thread::scope(|s| {
+        let handles: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let handle = s.spawn(move || {
+                    chunk.iter().map(|p| (p.clone(), parse_search_file(p))).collect::<Vec<_>>()
+                });
+                (chunk, handle)
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|(chunk, h)| {
+                // a panic while parsing one of the files fails only the files of its chunk
+                h.join().unwrap_or_else(|_| {
+                    chunk
+                        .iter()
+                        .map(|p| {
+                            let e = format!("Failed to parse {}: a file in the same batch panicked", p.display());
+                            (p.clone(), Err(Error::Internal(e)))
+                        })
+                        .collect()
+                })
+            })
+            .collect()
+    })
+}
+
+fn parse_search_file(path: &Path) -> Result<Vec<SearchResult>, Error> {
+    let body = std::fs::read(path).map_err(|e| {
+        Error::Internal(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    parse_search_results_only(&decode_html(&body, None))
+}
+
+/// `decode_html` decodes a page using, in order of precedence, its byte order mark, the charset
+/// of its Content-Type header, or the charset its meta tags declare, falling back to UTF-8. Bytes
+/// that are invalid in the charset are replaced rather than failing the decode.
+///
+/// # Parameters
+///
+/// * `body` - The bytes of the page.
+/// * `content_type` - The Content-Type header the page was served with, if any.
+pub fn decode_html(body: &[u8], content_type: Option<&str>) -> String {
+    let encoding = content_type
+        .and_then(|c| find_charset(c.as_bytes()))
+        // meta tags must appear in the first 1024 bytes of the page
+        .or_else(|| find_charset(&body[..body.len().min(1024)]))
+        .and_then(|charset| encoding_rs::Encoding::for_label(charset.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    let (text, _, _) = encoding.decode(body);
+    text.into_owned()
+}
 
+// find_charset returns the value of the first `charset=` parameter in a Content-Type header or
+// the head of a page, e.g. `<meta charset="utf-8">`.
+fn find_charset(s: &[u8]) -> Option<String> {
+    let s = String::from_utf8_lossy(s).to_ascii_lowercase();
+    let start = s.find("charset=")? + "charset=".len();
+    let charset: String = s[start..]
+        .trim_start_matches(['"', '\''])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+        .collect();
+    match charset.is_empty() {
+        true => None,
+        false => Some(charset),
+    }
+}
+
+fn parse_search_rows(document: &Html) -> Result<Vec<SearchResult>, Error> {
     // The current page places the results in a table within a div container in
     let selector = Selector::parse(r#"div#tableContainer tr"#)
         .map_err(|e| Error::Parsing(e.to_string()))?;
@@ -31,23 +182,21 @@ pub fn parse_search_results(html: &str) -> Result<Option<SearchPage>, Error> {
 
         let (update_id, row_id) = parse_search_row_id(id)?;
         let title = get_search_row_text(&row, SearchResColumn::Title, update_id, row_id)?;
+        let last_modified_raw =
+            get_search_row_text(&row, SearchResColumn::LastUpdated, update_id, row_id)?;
         results.push(SearchResult {
             title: title.to_string(),
-            id: update_id.to_string(),
-            kb: parse_kb_from_string(title)?,
+            id: update_id.parse()?,
+            kb: parse_kb_from_string(&title),
             product: get_search_row_text(&row, SearchResColumn::Product, update_id, row_id)?,
-            classification: get_search_row_text(
+            classification: normalize_classification(&get_search_row_text(
                 &row,
                 SearchResColumn::Classification,
                 update_id,
                 row_id,
-            )?,
-            last_modified: parse_update_date(get_search_row_text(
-                &row,
-                SearchResColumn::LastUpdated,
-                update_id,
-                row_id,
-            )?)?,
+            )?),
+            last_modified: parse_update_date(last_modified_raw.clone())?,
+            last_modified_raw,
             version: parse_optional_string(get_search_row_text(
                 &row,
                 SearchResColumn::Version,
@@ -68,44 +217,44 @@ pub fn parse_search_results(html: &str) -> Result<Option<SearchPage>, Error> {
         });
     }
 
-    if results.is_empty() {
-        return Ok(None);
-    }
-
-    Ok(Some((
-        SearchPageMeta {
-            // this can always be the next page, if there aren't more results we just won't
-            // make another request
-            event_target: "ctl00$catalogBody$nextPageLinkText".to_string(),
-            event_argument: get_element_attr(&document, "#__EVENTARGUMENT", "value")
-                .unwrap_or_else(|_| "".to_string()),
-            event_validation: get_element_attr(&document, "#__EVENTVALIDATION", "value")
-                .unwrap_or_else(|_| "".to_string()),
-            view_state: get_element_attr(&document, "#__VIEWSTATE", "value")?,
-            view_state_generator: get_element_attr(&document, "#__VIEWSTATEGENERATOR", "value")
-                .unwrap_or_else(|_| "".to_string()),
-            // If this element exists, there is a next page
-            pagination: parse_page_count_metadata(&document)?,
-        },
-        results,
-    )))
+    Ok(results)
 }
 
 pub fn parse_update_details(html: &str) -> Result<Update, Error> {
     let document = Html::parse_document(html);
+    parse_hidden_error_page(html)?;
+
     // The current page places the results in a table within a div container in
+    let last_modified_raw = select_with_paths(&document, DATE_PATHS)?;
+    let title = select_with_paths(&document, TITLE_PATHS)?;
+    let kb = clean_nested_div_text(select_with_path(&document, "div#kbDiv")?)?;
+    let description = select_with_path(&document, "#ScopedViewHandler_desc")?;
+    // the catalog leaves the architecture empty for many updates that name it in the title
+    let architecture = parse_optional_string(clean_nested_div_text(select_with_path(
+        &document, "#archDiv",
+    )?)?)
+    .or_else(|| parse_architecture_from_title(&title));
+    let reboot_behaviors = parse_reboot_behaviors(select_with_path(
+        &document,
+        "#ScopedViewHandler_rebootBehavior",
+    )?)?;
+    let (supersedes, supersedes_truncated) = get_update_supercedes_updates(&document)?;
+    let (superseded_by, superseded_by_truncated) = get_update_superseded_by_updates(&document)?;
     let u = Update {
-        title: select_with_path(&document, "#ScopedViewHandler_titleText")?,
-        id: select_with_path(&document, "#ScopedViewHandler_UpdateID")?,
-        kb: clean_nested_div_text(select_with_path(&document, "div#kbDiv")?)?,
-        classification: clean_nested_div_text(select_with_path(&document, "#classificationDiv")?)?,
-        last_modified: parse_update_date(select_with_path(&document, "#ScopedViewHandler_date")?)?,
-        size: parse_size_from_mb_string(select_with_path(&document, "#ScopedViewHandler_size")?)?,
-        description: select_with_path(&document, "#ScopedViewHandler_desc")?,
-        architecture: parse_optional_string(clean_nested_div_text(select_with_path(
-            &document, "#archDiv",
-        )?)?),
+        kb_numbers: parse_kb_numbers(&kb, &[&title, &description]),
+        title,
+        search_title: None,
+        id: select_with_paths(&document, UPDATE_ID_PATHS)?.parse()?,
+        kb,
+        classification: parse_details_classification(&document)?,
+        last_modified: parse_update_date(last_modified_raw.clone())?,
+        last_modified_raw,
+        size: parse_size_from_mb_string(select_with_paths(&document, SIZE_PATHS)?)?,
+        description,
+        description_links: get_links(&document, "#ScopedViewHandler_desc a")?,
+        architecture,
         supported_products: parse_nested_div_list(&document, "#productsDiv")?,
+        operating_systems: parse_nested_div_list(&document, "#operatingSystemsDiv")?,
         supported_languages: parse_nested_div_list(&document, "#languagesDiv")?,
         msrc_number: parse_optional_string(clean_nested_div_text(select_with_path(
             &document,
@@ -115,17 +264,18 @@ pub fn parse_update_details(html: &str) -> Result<Update, Error> {
             &document,
             "#ScopedViewHandler_msrcSeverity",
         )?),
+        kb_article_url: get_optional_link(&document, "div#kbDiv a")?,
+        security_bulletin_url: get_optional_link(&document, "#securityBullitenDiv a")?,
         info_url: Url::parse(&select_with_path(&document, "#moreInfoDiv a")?)
             .map_err(|e| Error::Parsing(e.to_string()))?,
-        support_url: Url::parse(
-            // There is a typo in the ID of this element 'suportUrlDiv'
-            &select_with_path(&document, "#suportUrlDiv a")?,
-        )
+        more_info_urls: get_links(&document, "#moreInfoDiv a")?,
+        support_url: Url::parse(&select_with_paths(&document, SUPPORT_URL_PATHS)?)
             .map_err(|e| Error::Parsing(e.to_string()))?,
-        reboot_behavior: parse_reboot_behavior(select_with_path(
-            &document,
-            "#ScopedViewHandler_rebootBehavior",
-        )?)?,
+        support_urls: get_links(&document, &SUPPORT_URL_PATHS.join(", "))?,
+        reboot_behavior: *reboot_behaviors
+            .first()
+            .ok_or(Error::Parsing("Failed to find reboot behavior".to_string()))?,
+        reboot_behaviors,
         requires_user_input: parse_yes_no_bool(select_with_path(
             &document,
             "#ScopedViewHandler_userInput",
@@ -146,13 +296,73 @@ pub fn parse_update_details(html: &str) -> Result<Update, Error> {
             &document,
             "#uninstallStepsDiv div",
         )?),
-        supersedes: get_update_supercedes_updates(&document)?,
-        superseded_by: get_update_superseded_by_updates(&document)?,
+        driver_info: parse_driver_info(&document)?,
+        supersedes,
+        supersedes_truncated,
+        superseded_by,
+        superseded_by_truncated,
     };
 
     Ok(u)
 }
 
+/// `is_blank_update_page` returns true if the details page has a blank title or update id, as
+/// when the catalog serves a half-rendered page. A page missing either element is not blank, as
+/// it is not a details page.
+pub fn is_blank_update_page(html: &str) -> bool {
+    let document = Html::parse_document(html);
+    [TITLE_PATHS, UPDATE_ID_PATHS]
+        .iter()
+        .any(|paths| select_with_paths(&document, paths).is_ok_and(|t| t.is_empty()))
+}
+
+pub fn parse_update_summary(html: &str) -> Result<UpdateSummary, Error> {
+    let document = Html::parse_document(html);
+    parse_hidden_error_page(html)?;
+    Ok(UpdateSummary {
+        title: select_with_paths(&document, TITLE_PATHS)?,
+        id: select_with_paths(&document, UPDATE_ID_PATHS)?.parse()?,
+        kb: clean_nested_div_text(select_with_path(&document, "div#kbDiv")?)?,
+        classification: parse_details_classification(&document)?,
+        last_modified: parse_update_date(select_with_paths(&document, DATE_PATHS)?)?,
+        size: parse_size_from_mb_string(select_with_paths(&document, SIZE_PATHS)?)?,
+    })
+}
+
+/// `parse_update_fields` parses only the selected sections of the update details page, so
+/// sections that were not selected can't cause the parse to fail.
+pub fn parse_update_fields(html: &str, fields: UpdateFields) -> Result<PartialUpdate, Error> {
+    let document = Html::parse_document(html);
+    parse_hidden_error_page(html)?;
+    let mut update = PartialUpdate::default();
+    if fields.contains(UpdateFields::TITLE) {
+        update.title = Some(select_with_paths(&document, TITLE_PATHS)?);
+    }
+    if fields.contains(UpdateFields::KB) {
+        update.kb = Some(clean_nested_div_text(select_with_path(&document, "div#kbDiv")?)?);
+    }
+    if fields.contains(UpdateFields::CLASSIFICATION) {
+        update.classification = Some(parse_details_classification(&document)?);
+    }
+    if fields.contains(UpdateFields::LAST_MODIFIED) {
+        update.last_modified = Some(parse_update_date(select_with_paths(&document, DATE_PATHS)?)?);
+    }
+    if fields.contains(UpdateFields::SIZE) {
+        update.size = Some(parse_size_from_mb_string(select_with_paths(&document, SIZE_PATHS)?)?);
+    }
+    if fields.contains(UpdateFields::SUPERSEDES) {
+        let (supersedes, truncated) = get_update_supercedes_updates(&document)?;
+        update.supersedes = Some(supersedes);
+        update.supersedes_truncated = Some(truncated);
+    }
+    if fields.contains(UpdateFields::SUPERSEDED_BY) {
+        let (superseded_by, truncated) = get_update_superseded_by_updates(&document)?;
+        update.superseded_by = Some(superseded_by);
+        update.superseded_by_truncated = Some(truncated);
+    }
+    Ok(update)
+}
+
 // parse_hidden_error_page handles the case where the Microsoft Update Catalog returns a 200
 // but the page contains an error message. This is a 500 from what I've seen so far.
 fn parse_hidden_error_page(html: &str) -> Result<(), Error> {
@@ -201,6 +411,39 @@ fn get_element_attr(document: &Html, path: &str, attr: &str) -> Result<String, E
         .map(|s| s.to_string())
 }
 
+/// `get_optional_link` returns the href of the first anchor matching the selector, resolved
+/// against the catalog base url, or `None` if there is no such anchor.
+fn get_optional_link(document: &Html, path: &str) -> Result<Option<Url>, Error> {
+    let selector = Selector::parse(path).map_err(|e| Error::Parsing(e.to_string()))?;
+    match document
+        .select(&selector)
+        .next()
+        .and_then(|e| e.value().attr("href"))
+    {
+        Some(href) => resolve_catalog_link(href).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// `get_links` returns the hrefs of all anchors matching the selector, resolved against the
+/// catalog base url.
+fn get_links(document: &Html, path: &str) -> Result<Vec<Url>, Error> {
+    let selector = Selector::parse(path).map_err(|e| Error::Parsing(e.to_string()))?;
+    document
+        .select(&selector)
+        .filter_map(|e| e.value().attr("href"))
+        .map(resolve_catalog_link)
+        .collect()
+}
+
+/// `resolve_catalog_link` parses an href from a catalog page, resolving relative links against
+/// the catalog base url.
+fn resolve_catalog_link(href: &str) -> Result<Url, Error> {
+    Url::parse(CATALOG_BASE_URL)
+        .and_then(|base| base.join(href.trim()))
+        .map_err(|e| Error::Parsing(format!("Failed to parse link '{}': {}", href, e)))
+}
+
 fn select_with_path(document: &Html, path: &str) -> Result<String, Error> {
     let selector = Selector::parse(path).map_err(|e| Error::Parsing(e.to_string()))?;
     document
@@ -213,22 +456,40 @@ fn select_with_path(document: &Html, path: &str) -> Result<String, Error> {
         .and_then(|e| get_element_text(&e))
 }
 
+// select_with_paths returns the text of the first element matching one of the paths, trying the
+// paths in order.
+fn select_with_paths(document: &Html, paths: &[&str]) -> Result<String, Error> {
+    paths
+        .iter()
+        .find_map(|path| select_with_path(document, path).ok())
+        .ok_or(Error::Parsing(format!(
+            "Failed to find element with selectors '{}'",
+            paths.join("', '")
+        )))
+}
+
 fn clean_nested_div_text(text: String) -> Result<String, Error> {
     Ok(text
         .split('\n')
-        .last()
+        .next_back()
         .ok_or(Error::Parsing("Failed to clean div text".to_string()))?
         .trim()
         .to_string())
 }
 
+// parse_nested_div_list returns an empty list if the section is missing, as it is for some
+// updates that list no products or languages.
 fn parse_nested_div_list(document: &Html, path: &str) -> Result<Vec<String>, Error> {
-    Ok(select_with_path(document, path)?
+    let text = match select_with_path(document, path) {
+        Ok(text) => text,
+        Err(_) => return Ok(vec![]),
+    };
+    Ok(text
         .split('\n')
         .filter_map(|s| {
             let s = s.trim();
             // filter the first label element and empty string/rows
-            if s.is_empty() || s.ends_with(':') || s == "," {
+            if s.is_empty() || s.ends_with(':') || s == "," || s == "n/a" {
                 None
             } else {
                 Some(s.to_string())
@@ -237,6 +498,44 @@ fn parse_nested_div_list(document: &Html, path: &str) -> Result<Vec<String>, Err
         .collect())
 }
 
+/// `normalize_classification` collapses the whitespace in a classification, so the search results
+/// and details pages, which lay it out differently, produce the same value.
+fn normalize_classification(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// parse_details_classification parses the classification that follows its label on the details
+// page, which can wrap across lines.
+fn parse_details_classification(document: &Html) -> Result<String, Error> {
+    let text = select_with_path(document, "#classificationDiv")?;
+    let value = text.split_once(':').map_or(text.as_str(), |(_, v)| v);
+    Ok(normalize_classification(value))
+}
+
+/// `normalize_product` lowercases a product name and collapses the repeated whitespace the catalog
+/// includes in product names, e.g. `Windows 10,  version 1903 and later`, so they can be compared.
+pub fn normalize_product(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// `parse_architecture_from_title` returns the architecture a title names in the form
+/// `for x64-based Systems`, e.g. `x64`, `x86`, or `ARM64`, or `None` if the title does not name
+/// one.
+pub fn parse_architecture_from_title(title: &str) -> Option<String> {
+    let lower = title.to_lowercase();
+    let end = lower.find("-based systems")?;
+    let start = lower[..end].rfind(' ').map_or(0, |i| i + 1);
+    let arch = &lower[start..end];
+    match arch {
+        "" => None,
+        _ if arch.starts_with("arm") => Some(arch.to_uppercase()),
+        _ => Some(arch.to_string()),
+    }
+}
+
 fn parse_optional_string(s: String) -> Option<String> {
     match s.as_str() {
         "n/a" => None,
@@ -244,6 +543,14 @@ fn parse_optional_string(s: String) -> Option<String> {
     }
 }
 
+/// `parse_reboot_behaviors` parses a reboot behavior that may list more than one value separated
+/// by slashes, e.g. `Can request restart / Recommended`.
+fn parse_reboot_behaviors(s: String) -> Result<Vec<RebootBehavior>, Error> {
+    s.split('/')
+        .map(|b| parse_reboot_behavior(b.trim().to_string()))
+        .collect()
+}
+
 fn parse_reboot_behavior(s: String) -> Result<RebootBehavior, Error> {
     match s.as_str() {
         "Required" => Ok(RebootBehavior::Required),
@@ -270,27 +577,92 @@ fn parse_yes_no_bool(s: String) -> Result<bool, Error> {
     }
 }
 
+/// `parse_driver_info` parses the driver details section, which is only present on the details
+/// page of driver updates.
+fn parse_driver_info(document: &Html) -> Result<Option<DriverInfo>, Error> {
+    if select_with_path(document, "#driverVerVersionDiv").is_err() {
+        return Ok(None);
+    }
+    Ok(Some(DriverInfo {
+        version: clean_nested_div_text(select_with_path(document, "#driverVerVersionDiv")?)?,
+        date: parse_update_date(clean_nested_div_text(select_with_path(document, "#driverVerDateDiv")?)?)?,
+        provider: clean_nested_div_text(select_with_path(document, "#driverProviderDiv")?)?,
+        class: clean_nested_div_text(select_with_path(document, "#driverClassDiv")?)?,
+    }))
+}
+
+/// `UPDATE_DATE_FORMATS` are the date formats the catalog has been seen using, in the order they
+/// are tried. `%m/%d/%Y` is by far the most common. Each two-digit year format is tried before
+/// the matching four-digit year format, as `%Y` would otherwise parse `23` as the year 23.
+const UPDATE_DATE_FORMATS: [&str; 7] = [
+    "%m/%d/%y",
+    "%m/%d/%Y",
+    "%m-%d-%y",
+    "%m-%d-%Y",
+    "%m.%d.%y",
+    "%m.%d.%Y",
+    "%Y-%m-%d",
+];
+
 fn parse_update_date(date: String) -> Result<chrono::NaiveDate, Error> {
-    chrono::NaiveDate::parse_from_str(date.as_str(), "%m/%d/%Y")
-        .map_err(|e| Error::Parsing(e.to_string()))
+    UPDATE_DATE_FORMATS
+        .iter()
+        .find_map(|f| chrono::NaiveDate::parse_from_str(date.trim(), f).ok())
+        .ok_or(Error::Parsing(format!(
+            "Failed to parse date from '{}', tried formats: {}",
+            date,
+            UPDATE_DATE_FORMATS.join(", ")
+        )))
 }
 
-fn parse_kb_from_string(s: String) -> Result<String, Error> {
-    Ok(s.split("(KB")
-        .last()
-        .ok_or(Error::Parsing(
-            "Failed to find KB number in title".to_string()
-        ))?
-        .split(')')
-        .next()
-        .ok_or(Error::Parsing(
-            "Failed to parse KB number from title".to_string()
-        ))?
-        .to_string()
-    )
+// The catalog does not list a date next to superseding updates, but the titles of monthly
+// updates begin with their release month, e.g. `2023-09 Cumulative Update for ...`.
+fn parse_release_month_from_title(title: &str) -> Option<chrono::NaiveDate> {
+    let month = title.split_whitespace().next()?;
+    chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").ok()
+}
+
+// parse_kb_from_string returns the KB number in the last parenthesized `(KB...)` of a title, or
+// None if the title does not include one, as for some feature packs and tools.
+fn parse_kb_from_string(s: &str) -> Option<String> {
+    let start = s.rfind("(KB")? + "(KB".len();
+    let kb = &s[start..start + s[start..].find(')')?];
+    if kb.is_empty() || !kb.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(kb.to_string())
 }
 
+/// `parse_kb_numbers` returns the unique KB numbers from the KB article field followed by any
+/// `KB1234567` tokens found in the given strings, in the order they were found.
+fn parse_kb_numbers(kb: &str, sources: &[&str]) -> Vec<String> {
+    let mut kbs: Vec<String> = vec![];
+    let tokens = kb
+        .split(',')
+        .map(|s| s.trim())
+        .chain(sources.iter().flat_map(|source| {
+            source
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter_map(|token| token.strip_prefix("KB"))
+        }));
+    for token in tokens {
+        if token.is_empty() || !token.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        if !kbs.iter().any(|k| k == token) {
+            kbs.push(token.to_string());
+        }
+    }
+    kbs
+}
+
+/// `parse_size_from_mb_string` parses a size such as `316.2 MB` into bytes. Sizes the catalog
+/// leaves empty or lists as `n/a` are parsed as 0.
 fn parse_size_from_mb_string(s: String) -> Result<u64, Error> {
+    let s = s.trim();
+    if s.is_empty() || s.eq_ignore_ascii_case("n/a") {
+        return Ok(0);
+    }
     Ok(s.split(' ').next()
         .ok_or(Error::Parsing("Failed to parse size from MB string".to_string()))?
         // There's a decimal point in the size, cheap way to remove it
@@ -323,12 +695,26 @@ fn clean_string_with_newlines(s: String) -> String {
         .join(" ")
 }
 
-fn get_update_superseded_by_updates(document: &Html) -> Result<Vec<SupersededByUpdate>, Error> {
+/// `is_truncation_indicator` returns true for the rows the catalog appends to a list of updates
+/// that was cut short, such as "See all 12 updates" or "and 14 more...". Update rows always
+/// include a KB number, so rows without one are not mistaken for updates.
+fn is_truncation_indicator(text: &str) -> bool {
+    let text = text.to_lowercase();
+    !text.contains("(kb")
+        && (text.contains("more") || text.contains("see all") || text.contains("view all"))
+}
+
+fn get_update_superseded_by_updates(document: &Html) -> Result<(Vec<SupersededByUpdate>, bool), Error> {
     let selector = Selector::parse(r#"div#supersededbyInfo div a"#)
         .map_err(|e| Error::Parsing(e.to_string()))?;
     let mut superseded_by = vec![];
+    let mut truncated = false;
     for row in document.select(&selector) {
         let title = clean_string_with_newlines(get_element_text(&row)?);
+        if is_truncation_indicator(&title) {
+            truncated = true;
+            continue;
+        }
         let id = row
             .value()
             .attr("href")
@@ -338,25 +724,31 @@ fn get_update_superseded_by_updates(document: &Html) -> Result<Vec<SupersededByU
             .trim_start_matches("ScopedViewInline.aspx?updateid=");
         superseded_by.push(SupersededByUpdate {
             title: title.to_string(),
-            kb: parse_kb_from_string(title)?,
-            id: id.to_string(),
+            kb: parse_kb_from_string(&title),
+            id: id.parse()?,
+            release_month: parse_release_month_from_title(&title),
         });
     }
-    Ok(superseded_by)
+    Ok((superseded_by, truncated))
 }
 
-fn get_update_supercedes_updates(document: &Html) -> Result<Vec<SupersedesUpdate>, Error> {
+fn get_update_supercedes_updates(document: &Html) -> Result<(Vec<SupersedesUpdate>, bool), Error> {
     let selector = Selector::parse(r#"div#supersedesInfo div"#)
         .map_err(|e| Error::Parsing(e.to_string()))?;
     let mut supersedes = vec![];
+    let mut truncated = false;
     for row in document.select(&selector) {
         let title = clean_string_with_newlines(get_element_text(&row)?);
+        if is_truncation_indicator(&title) {
+            truncated = true;
+            continue;
+        }
         supersedes.push(SupersedesUpdate {
             title: title.to_string(),
-            kb: parse_kb_from_string(title)?,
+            kb: parse_kb_from_string(&title),
         });
     }
-    Ok(supersedes)
+    Ok((supersedes, truncated))
 }
 
 fn get_search_row_selector(
@@ -413,7 +805,9 @@ fn get_search_row_text(
 
 /// `parse_page_count_metadata` parses the page count and result count from the search results page.
 /// Format: `1 - 25 of 761 (page 1 of 31)`
-fn parse_page_count_metadata(document: &Html) -> Result<SearchPagePaginationMeta, Error> {
+fn parse_page_count_metadata(document: &Html, fields: &SearchFormFields) -> Result<SearchPagePaginationMeta, Error> {
+    // the id of an ASP.NET control is its name with the separators replaced
+    let next_page_link_path = format!("#{}", fields.next_page_event_target.replace('$', "_"));
     let selector = Selector::parse(r#"span#ctl00_catalogBody_searchDuration"#)
         .map_err(|e| Error::Parsing(e.to_string()))?;
     let text = document
@@ -445,9 +839,10 @@ fn parse_page_count_metadata(document: &Html) -> Result<SearchPagePaginationMeta
         .parse::<i16>().map_err(|e| Error::Parsing(format!("failed to parse page count from '{}': {:?}", text, e)))?;
 
     Ok(SearchPagePaginationMeta {
-        has_next_page: select_with_path(document, "#ctl00_catalogBody_nextPageLinkText").is_ok(),
+        has_next_page: select_with_path(document, &next_page_link_path).is_ok(),
+        initialized: true,
         too_many_results: select_with_path(document, "#ctl00_catalogBody_moreResults").is_ok(),
-        page_size: 25, // always 25 results per page
+        page_size: SEARCH_PAGE_SIZE,
         page_count,
         current_page,
         result_count,
@@ -483,6 +878,7 @@ mod test {
                     view_state_generator: "".to_string(),
                     pagination: SearchPagePaginationMeta {
                         has_next_page: false,
+                        initialized: true,
                         too_many_results: false,
                         current_page: 1,
                         page_size: 25,
@@ -493,31 +889,34 @@ mod test {
                  vec![
                      SearchResult {
                          title: "Security Update For Exchange Server 2019 CU12 (KB5030524)".to_string(),
-                         id: "56a97db8-1478-4860-a935-7996c78d10be".to_string(),
-                         kb: "5030524".to_string(),
+                         id: "56a97db8-1478-4860-a935-7996c78d10be".parse().expect("Failed to parse update id"),
+                         kb: Some("5030524".to_string()),
                          product: "Exchange Server 2019".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 8, 15).expect("Failed to parse date for test data"),
+                         last_modified_raw: "8/15/2023".to_string(),
                          version: None,
                          size: 168715878,
                      },
                      SearchResult {
                          title: "Security Update For Exchange Server 2019 CU13 (KB5030524)".to_string(),
-                         id: "70c08420-a012-4f5b-9b48-95a6b177d34a".to_string(),
-                         kb: "5030524".to_string(),
+                         id: "70c08420-a012-4f5b-9b48-95a6b177d34a".parse().expect("Failed to parse update id"),
+                         kb: Some("5030524".to_string()),
                          product: "Exchange Server 2019".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 8, 15).expect("Failed to parse date for test data"),
+                         last_modified_raw: "8/15/2023".to_string(),
                          version: None,
                          size: 168715878,
                      },
                      SearchResult {
                          title: "Security Update For Exchange Server 2016 CU23 (KB5030524)".to_string(),
-                         id: "a08b526d-3947-4ddd-ba72-a8244b39c611".to_string(),
-                         kb: "5030524".to_string(),
+                         id: "a08b526d-3947-4ddd-ba72-a8244b39c611".parse().expect("Failed to parse update id"),
+                         kb: Some("5030524".to_string()),
                          product: "Exchange Server 2016".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 8, 15).expect("Failed to parse date for test data"),
+                         last_modified_raw: "8/15/2023".to_string(),
                          version: None,
                          size: 165045862,
                      },
@@ -534,6 +933,7 @@ mod test {
                     view_state_generator: "".to_string(),
                     pagination: SearchPagePaginationMeta {
                         has_next_page: false,
+                        initialized: true,
                         too_many_results: false,
                         current_page: 1,
                         page_size: 25,
@@ -544,121 +944,133 @@ mod test {
                  vec![
                      SearchResult {
                          title: "2023-09 Cumulative Update for Windows 10 Version 21H2 for x64-based Systems (KB5030211)".to_string(),
-                         id: "453112b9-83bb-403c-9263-018ffe515016".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "453112b9-83bb-403c-9263-018ffe515016".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10 LTSB, Windows 10,  version 1903 and later".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 802160640,
                      },
                      SearchResult {
                          title: "2023-09 Dynamic Cumulative Update for Windows 10 Version 21H2 for ARM64-based Systems (KB5030211)".to_string(),
-                         id: "97fcb38d-dcb2-41e7-b75b-96327b676926".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "97fcb38d-dcb2-41e7-b75b-96327b676926".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10 and later GDR-DU".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 811912396,
                      },
                      SearchResult {
                          title: "2023-09 Dynamic Cumulative Update for Windows 10 Version 21H2 for x64-based Systems (KB5030211)".to_string(),
-                         id: "0aec0f4e-5228-4f59-bfc4-08e3c3cd32bb".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "0aec0f4e-5228-4f59-bfc4-08e3c3cd32bb".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10 and later GDR-DU".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 785697996,
                      },
                      SearchResult {
                          title: "2023-09 Cumulative Update for Windows 10 Version 21H2 for ARM64-based Systems (KB5030211)".to_string(),
-                         id: "c0e5f33a-0509-4891-9935-438d061b806e".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "c0e5f33a-0509-4891-9935-438d061b806e".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10 LTSB, Windows 10,  version 1903 and later".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 827221606,
                      },
                      SearchResult {
                          title: "2023-09 Dynamic Cumulative Update for Windows 10 Version 22H2 for ARM64-based Systems (KB5030211)".to_string(),
-                         id: "cdf18eed-1b04-4211-87a0-d0e865ea16ba".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "cdf18eed-1b04-4211-87a0-d0e865ea16ba".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10 and later GDR-DU".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 811912396,
                      },
                      SearchResult {
                          title: "2023-09 Cumulative Update for Windows 10 Version 22H2 for ARM64-based Systems (KB5030211)".to_string(),
-                         id: "7ef071f6-f25c-457a-bd10-d0dcfb149cd0".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "7ef071f6-f25c-457a-bd10-d0dcfb149cd0".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10,  version 1903 and later".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 827221606,
                      },
                      SearchResult {
                          title: "2023-09 Cumulative Update for Windows 10 Version 22H2 for x86-based Systems (KB5030211)".to_string(),
-                         id: "7969059c-6aad-4562-a40f-8c764af68e86".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "7969059c-6aad-4562-a40f-8c764af68e86".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10,  version 1903 and later".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 439772774,
                      },
                      SearchResult {
                          title: "2023-09 Cumulative Update for Windows 10 Version 21H2 for x86-based Systems (KB5030211)".to_string(),
-                         id: "1e3b4e94-a544-4137-8fba-8ae1a2853a95".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "1e3b4e94-a544-4137-8fba-8ae1a2853a95".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10 LTSB, Windows 10,  version 1903 and later".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 439772774,
                      },
                      SearchResult {
                          title: "2023-09 Cumulative Update for Windows 10 Version 22H2 for x64-based Systems (KB5030211)".to_string(),
-                         id: "4aec4d66-a06c-4544-9f79-55ace822e015".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "4aec4d66-a06c-4544-9f79-55ace822e015".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10,  version 1903 and later".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 802160640,
                      },
                      SearchResult {
                          title: "2023-09 Dynamic Cumulative Update for Windows 10 Version 22H2 for x86-based Systems (KB5030211)".to_string(),
-                         id: "403e7eb7-6022-4197-bf50-65aeca4ff368".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "403e7eb7-6022-4197-bf50-65aeca4ff368".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10 and later GDR-DU".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 432118169,
                      },
                      SearchResult {
                          title: "2023-09 Dynamic Cumulative Update for Windows 10 Version 21H2 for x86-based Systems (KB5030211)".to_string(),
-                         id: "590018dd-2c62-42b7-bd0b-e065f9283f36".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "590018dd-2c62-42b7-bd0b-e065f9283f36".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10 and later GDR-DU".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 432118169,
                      },
                      SearchResult {
                          title: "2023-09 Dynamic Cumulative Update for Windows 10 Version 22H2 for x64-based Systems (KB5030211)".to_string(),
-                         id: "aaba42ce-ba39-4d0a-94af-0f51e68d5bfb".to_string(),
-                         kb: "5030211".to_string(),
+                         id: "aaba42ce-ba39-4d0a-94af-0f51e68d5bfb".parse().expect("Failed to parse update id"),
+                         kb: Some("5030211".to_string()),
                          product: "Windows 10 and later GDR-DU".to_string(),
                          classification: "Security Updates".to_string(),
                          last_modified: NaiveDate::from_ymd_opt(2023, 9, 12).expect("Failed to parse date for test data"),
+                         last_modified_raw: "9/12/2023".to_string(),
                          version: None,
                          size: 785697996,
                      },
@@ -668,7 +1080,7 @@ mod test {
         ];
 
         for tc in test_cases.iter() {
-            let results = parse_search_results(tc.0.as_str());
+            let results = parse_search_results(tc.0.as_str(), &SearchFormFields::default());
             assert!(results.is_ok());
             let page = results.unwrap();
             assert!(page.is_some());
@@ -689,6 +1101,193 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_parse_search_no_results() {
+        let res = parse_search_results(&load_test_data!("msuc_search_no_results.html"), &SearchFormFields::default());
+        assert!(res.expect("Failed to parse search results").is_none());
+        let res = parse_search_results(&load_test_data!("msuc_search_empty_table.html"), &SearchFormFields::default());
+        assert!(matches!(res, Err(Error::Parsing(ref e)) if e.contains("rows")));
+        let res = parse_search_results(&load_test_data!("msuc_update_details.html"), &SearchFormFields::default());
+        assert!(matches!(res, Err(Error::Parsing(_))));
+    }
+
+    #[test]
+    fn test_parse_update_date() {
+        let expected = NaiveDate::from_ymd_opt(2023, 4, 25).expect("Failed to create date");
+        for date in ["4/25/2023", "04/25/2023", "4/25/23", "4-25-2023", "04-25-23", "4.25.2023", "2023-04-25", " 4/25/2023 "] {
+            assert_eq!(Some(expected), parse_update_date(date.to_string()).ok(), "{}", date);
+        }
+        let err = parse_update_date("25 April 2023".to_string()).expect_err("Expected the date to fail to parse");
+        assert!(matches!(err, Error::Parsing(ref e) if e.contains("25 April 2023") && e.contains("%m/%d/%Y")));
+        assert!(parse_update_date("2023-09-26T00:00:00".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_parse_kb_from_string() {
+        let test_cases = [
+            ("Security Update For Exchange Server 2019 CU12 (KB5030524)", Some("5030524".to_string())),
+            ("Update for Windows (KB123) (KB5030524)", Some("5030524".to_string())),
+            ("Microsoft Exchange Server 2016 CU23 Management Tools", None),
+            ("Windows Feature Pack (Media Feature Pack)", None),
+            ("Update (KB)", None),
+            ("Update (KB5030524", None),
+        ];
+        for (title, expected) in test_cases {
+            assert_eq!(expected, parse_kb_from_string(title), "{}", title);
+        }
+    }
+
+    #[test]
+    fn test_parse_release_month_from_title() {
+        let test_cases = [
+            ("2023-09 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5030219)", NaiveDate::from_ymd_opt(2023, 9, 1)),
+            ("2023-13 Cumulative Update for Windows 11 (KB5030219)", None),
+            ("Security Update For Exchange Server 2019 CU12 (KB5030524)", None),
+            ("", None),
+        ];
+        for (title, expected) in test_cases {
+            assert_eq!(expected, parse_release_month_from_title(title), "{}", title);
+        }
+    }
+
+    #[test]
+    fn test_parse_architecture_from_title() {
+        let results = parse_search_results_only(&load_test_data!("msuc_double_digit_rows.html"))
+            .expect("Failed to parse search results");
+        let architectures: std::collections::BTreeSet<Option<String>> = results
+            .iter()
+            .filter(|r| r.kb == Some("5030211".to_string()))
+            .map(|r| r.architecture())
+            .collect();
+        assert_eq!(
+            std::collections::BTreeSet::from([
+                Some("ARM64".to_string()),
+                Some("x64".to_string()),
+                Some("x86".to_string()),
+            ]),
+            architectures
+        );
+        assert_eq!(Some("ARM64".to_string()), parse_architecture_from_title("Update for Windows 11 for arm64-based Systems"));
+        assert_eq!(None, parse_architecture_from_title("Security Update For Exchange Server 2019 CU12 (KB5030524)"));
+    }
+
+    #[test]
+    fn test_parse_search_results_without_kb() {
+        let results = parse_search_results_only(&load_test_data!("msuc_search_no_kb.html"))
+            .expect("Failed to parse search results");
+        assert_eq!(3, results.len());
+        assert_eq!(Some("5030524".to_string()), results[0].kb);
+        assert_eq!("Microsoft Exchange Server 2016 CU23 Management Tools", results[2].title);
+        assert_eq!(None, results[2].kb);
+    }
+
+    #[test]
+    fn test_parse_search_results_with_form_fields() {
+        let data = load_test_data!("msuc_search_with_next_page.html");
+        let renamed = data
+            .replace(r#"id="__VIEWSTATE""#, r#"id="__PAGESTATE""#)
+            .replace("ctl00_catalogBody_nextPageLinkText", "ctl01_catalogBody_nextLink");
+        let fields = SearchFormFields {
+            view_state: "__PAGESTATE".to_string(),
+            next_page_event_target: "ctl01$catalogBody$nextLink".to_string(),
+            ..SearchFormFields::default()
+        };
+        let (expected, _) = parse_search_results(&data, &SearchFormFields::default())
+            .expect("Failed to parse search results")
+            .expect("Expected search results");
+        let (meta, _) = parse_search_results(&renamed, &fields)
+            .expect("Failed to parse search results")
+            .expect("Expected search results");
+        assert_eq!(expected.view_state, meta.view_state);
+        assert_eq!("ctl01$catalogBody$nextLink", meta.event_target);
+        assert!(meta.pagination.has_next_page);
+        assert_eq!(
+            Some(&meta.view_state.as_str()),
+            meta.as_map(&fields).get("__PAGESTATE")
+        );
+        assert!(parse_search_results(&renamed, &SearchFormFields::default()).is_err());
+    }
+
+    #[test]
+    fn test_parse_classification_matches_search_results() {
+        let search = load_test_data!("msuc_small_result.html").replacen(
+            "Security Updates",
+            "Security\n                                                Updates",
+            1,
+        );
+        let details = load_test_data!("msuc_update_details.html").replacen(
+            "                                    Updates\n",
+            "                                    Security   Updates\n",
+            1,
+        );
+        let results = parse_search_results_only(&search).expect("Failed to parse search results");
+        let update = parse_update_details(&details).expect("Failed to parse update details");
+        let summary = parse_update_summary(&details).expect("Failed to parse update summary");
+        assert_eq!("Security Updates", results[0].classification);
+        assert_eq!(results[0].classification, update.classification);
+        assert_eq!(update.classification, summary.classification);
+    }
+
+    #[test]
+    fn test_parse_search_results_only() {
+        let test_cases = [
+            load_test_data!("msuc_small_result.html"),
+            load_test_data!("msuc_double_digit_rows.html"),
+            load_test_data!("msuc_search_with_next_page.html"),
+        ];
+        for data in test_cases.iter() {
+            let page = parse_search_results(data, &SearchFormFields::default())
+                .expect("Failed to parse search results")
+                .expect("Expected search results");
+            let results = parse_search_results_only(data);
+            assert!(results.is_ok());
+            assert_eq!(page.1, results.unwrap());
+        }
+    }
+
+    #[test]
+    fn test_parse_search_files() {
+        let dir = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test/"));
+        let paths = vec![
+            dir.join("msuc_small_result.html"),
+            dir.join("msuc_does_not_exist.html"),
+            dir.join("msuc_search_error_500.html"),
+            dir.join("msuc_double_digit_rows.html"),
+        ];
+        let results = parse_search_files(&paths);
+        assert_eq!(paths, results.iter().map(|r| r.0.clone()).collect::<Vec<_>>());
+        assert_eq!(3, results[0].1.as_ref().expect("Expected search results").len());
+        assert!(matches!(results[1].1, Err(Error::Internal(_))));
+        assert!(matches!(results[2].1, Err(Error::Msuc(_, _))));
+        assert_eq!(
+            parse_search_results_only(&load_test_data!("msuc_double_digit_rows.html")).expect("Failed to parse test data"),
+            *results[3].1.as_ref().expect("Expected search results"),
+        );
+    }
+
+    #[test]
+    fn test_parse_search_files_not_utf8() {
+        let mut body = load_test_data!("msuc_small_result.html").into_bytes();
+        // a byte that is invalid in UTF-8, as in a page saved as Windows-1252
+        body.extend_from_slice(b"<!-- Fran\xe7ais -->");
+        let path = std::env::temp_dir().join(format!("msuc_search_not_utf8_{}.html", std::process::id()));
+        std::fs::write(&path, body).expect("Failed to write test data");
+        let results = parse_search_files(std::slice::from_ref(&path));
+        std::fs::remove_file(&path).expect("Failed to remove test data");
+        assert_eq!(3, results[0].1.as_ref().expect("Expected search results").len());
+    }
+
+    #[test]
+    fn test_decode_html() {
+        let body = b"<html><head><meta charset=\"windows-1252\"></head><body>Fran\xe7ais</body></html>";
+        assert!(decode_html(body, None).contains("Fran\u{e7}ais"));
+        assert!(decode_html(body, Some("text/html; charset=ISO-8859-1")).contains("Fran\u{e7}ais"));
+        assert!(decode_html("Fran\u{e7}ais".as_bytes(), Some("text/html")).contains("Fran\u{e7}ais"));
+        assert!(decode_html(b"\xEF\xBB\xBFFran\xc3\xa7ais", Some("text/html; charset=windows-1252")).starts_with("Fran\u{e7}ais"));
+        assert_eq!(Some("utf-8".to_string()), find_charset(b"text/html; charset=\"UTF-8\""));
+        assert_eq!(None, find_charset(b"text/html"));
+    }
+
     #[test]
     fn test_parse_hidden_error_search_results() {
         let test_cases = [(
@@ -697,7 +1296,7 @@ mod test {
         )];
 
         for tc in test_cases.iter() {
-            let results = parse_search_results(tc.0.as_str());
+            let results = parse_search_results(tc.0.as_str(), &SearchFormFields::default());
             assert!(results.is_err());
             match results {
                 Err(e) => {
@@ -721,6 +1320,7 @@ mod test {
             view_state_generator: "BBBC20B8".to_string(),
             pagination: SearchPagePaginationMeta {
                 has_next_page: true,
+                initialized: true,
                 too_many_results: false,
                 current_page: 1,
                 page_size: 25,
@@ -729,7 +1329,7 @@ mod test {
             },
         };
 
-        let results = parse_search_results(data.as_str());
+        let results = parse_search_results(data.as_str(), &SearchFormFields::default());
         assert!(results.is_ok());
         let page = results.unwrap();
         assert!(page.is_some());
@@ -750,6 +1350,7 @@ mod test {
             view_state_generator: "BBBC20B8".to_string(),
             pagination: SearchPagePaginationMeta {
                 has_next_page: true,
+                initialized: true,
                 too_many_results: true,
                 current_page: 1,
                 page_size: 25,
@@ -758,7 +1359,7 @@ mod test {
             },
         };
 
-        let results = parse_search_results(data.as_str());
+        let results = parse_search_results(data.as_str(), &SearchFormFields::default());
         assert!(results.is_ok());
         let page = results.unwrap();
         assert!(page.is_some());
@@ -775,177 +1376,212 @@ mod test {
                 load_test_data!("msuc_update_details.html"),
                 Update {
                     title: "2023-04 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5025305)".to_string(),
-                    id: "1b0b70c0-191e-42f6-8808-c1b50deacb3b".to_string(),
+                    search_title: None,
+                    id: "1b0b70c0-191e-42f6-8808-c1b50deacb3b".parse().expect("Failed to parse update id"),
                     kb: "5025305".to_string(),
+                    kb_numbers: vec!["5025305".to_string()],
                     classification: "Updates".to_string(),
                     last_modified: NaiveDate::from_ymd_opt(2023, 4, 25).expect("Failed to parse date for test data"),
+                    last_modified_raw: "4/25/2023".to_string(),
                     size: 331559731,
                     description: "Install this update to resolve issues in Windows. For a complete listing of the issues that are included in this update, see the associated Microsoft Knowledge Base article for more information. After you install this item, you may have to restart your computer.".to_string(),
-                    architecture: None,
+                    description_links: vec![],
+                    architecture: Some("x64".to_string()),
                     supported_products: vec!["Windows 11".to_string()],
+                    operating_systems: vec![],
                     supported_languages: vec!["Arabic".to_string(), "Bulgarian".to_string(), "Czech".to_string(), "Danish".to_string(), "German".to_string(), "Greek".to_string(), "English".to_string(), "Spanish".to_string(), "Estonian".to_string(), "Finnish".to_string(), "French".to_string(), "Hebrew".to_string(), "Croatian".to_string(), "Hungarian".to_string(), "Italian".to_string(), "Japanese".to_string(), "Korean".to_string(), "Lithuanian".to_string(), "Latvian".to_string(), "Norwegian".to_string(), "Dutch".to_string(), "Polish".to_string(), "Portuguese (Brazil)".to_string(), "Portuguese (Portugal)".to_string(), "Romanian".to_string(), "Russian".to_string(), "Slovak".to_string(), "Slovenian".to_string(), "Serbian (Latin)".to_string(), "Swedish".to_string(), "Thai".to_string(), "Turkish".to_string(), "Ukrainian".to_string(), "Chinese (Simplified)".to_string(), "Chinese (Traditional)".to_string(), "all".to_string()],
                     msrc_number: None,
                     msrc_severity: None,
+                    kb_article_url: None,
+                    security_bulletin_url: None,
                     info_url: Url::parse("https://support.microsoft.com/help/5025305").expect("Failed to parse URL for test data"),
+                    more_info_urls: vec![Url::parse("https://support.microsoft.com/help/5025305").expect("Failed to parse URL for test data")],
                     support_url: Url::parse("https://support.microsoft.com/help/5025305").expect("Failed to parse URL for test data"),
+                    support_urls: vec![Url::parse("https://support.microsoft.com/help/5025305").expect("Failed to parse URL for test data")],
                     reboot_behavior: RebootBehavior::CanRequest,
+                    reboot_behaviors: vec![RebootBehavior::CanRequest],
                     requires_user_input: false,
                     is_exclusive_install: false,
                     requires_network_connectivity: false,
                     uninstall_notes: None,
                     uninstall_steps: None,
+                    driver_info: None,
                     supersedes: vec![
                         SupersedesUpdate {
                             title: "2023-04 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5025239)".to_string(),
-                            kb: "5025239".to_string(),
+                            kb: Some("5025239".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2023-02 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5022913) UUP".to_string(),
-                            kb: "5022913".to_string(),
+                            kb: Some("5022913".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2023-03 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5023778)".to_string(),
-                            kb: "5023778".to_string(),
+                            kb: Some("5023778".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2022-09 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5017389)".to_string(),
-                            kb: "5017389".to_string(),
+                            kb: Some("5017389".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2022-10 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5018427)".to_string(),
-                            kb: "5018427".to_string(),
+                            kb: Some("5018427".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2022-10 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5019509)".to_string(),
-                            kb: "5019509".to_string(),
+                            kb: Some("5019509".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2022-09 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5017321)".to_string(),
-                            kb: "5017321".to_string(),
+                            kb: Some("5017321".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2022-09 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5019311)".to_string(),
-                            kb: "5019311".to_string(),
+                            kb: Some("5019311".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2022-11 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5019980)".to_string(),
-                            kb: "5019980".to_string(),
+                            kb: Some("5019980".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2023-01 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5022303)".to_string(),
-                            kb: "5022303".to_string(),
+                            kb: Some("5022303".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2023-01 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5022360)".to_string(),
-                            kb: "5022360".to_string(),
+                            kb: Some("5022360".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2022-11 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5020044)".to_string(),
-                            kb: "5020044".to_string(),
+                            kb: Some("5020044".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2023-02 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5022913)".to_string(),
-                            kb: "5022913".to_string(),
+                            kb: Some("5022913".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2022-10 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5018496)".to_string(),
-                            kb: "5018496".to_string(),
+                            kb: Some("5018496".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2022-12 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5021255)".to_string(),
-                            kb: "5021255".to_string(),
+                            kb: Some("5021255".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2023-02 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5022845)".to_string(),
-                            kb: "5022845".to_string(),
+                            kb: Some("5022845".to_string()),
                         },
                         SupersedesUpdate {
                             title: "2023-03 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5023706)".to_string(),
-                            kb: "5023706".to_string(),
+                            kb: Some("5023706".to_string()),
                         },
                     ],
+                    supersedes_truncated: false,
                     superseded_by: vec![
                         SupersededByUpdate {
                             title: "2023-09 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5030219)".to_string(),
-                            kb: "5030219".to_string(),
-                            id: "03423c5a-458d-4cbe-b67e-d47bec7f3fb6".to_string(),
+                            kb: Some("5030219".to_string()),
+                            id: "03423c5a-458d-4cbe-b67e-d47bec7f3fb6".parse().expect("Failed to parse update id"),
+                            release_month: NaiveDate::from_ymd_opt(2023, 9, 1),
                         },
                         SupersededByUpdate {
                             title: "2023-08 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5029263)".to_string(),
-                            kb: "5029263".to_string(),
-                            id: "10b0cdce-d084-452d-b6a3-318a3ade0a6e".to_string(),
+                            kb: Some("5029263".to_string()),
+                            id: "10b0cdce-d084-452d-b6a3-318a3ade0a6e".parse().expect("Failed to parse update id"),
+                            release_month: NaiveDate::from_ymd_opt(2023, 8, 1),
                         },
                         SupersededByUpdate {
                             title: "2023-08 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5029351)".to_string(),
-                            kb: "5029351".to_string(),
-                            id: "1a1ab822-a9e3-4a00-abd5-a4fafbf02982".to_string(),
+                            kb: Some("5029351".to_string()),
+                            id: "1a1ab822-a9e3-4a00-abd5-a4fafbf02982".parse().expect("Failed to parse update id"),
+                            release_month: NaiveDate::from_ymd_opt(2023, 8, 1),
                         },
                         SupersededByUpdate {
                             title: "2023-07 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5028185)".to_string(),
-                            kb: "5028185".to_string(),
-                            id: "1f6417e4-a329-42c4-95e0-fa7d09bb6f90".to_string(),
+                            kb: Some("5028185".to_string()),
+                            id: "1f6417e4-a329-42c4-95e0-fa7d09bb6f90".parse().expect("Failed to parse update id"),
+                            release_month: NaiveDate::from_ymd_opt(2023, 7, 1),
                         },
                         SupersededByUpdate {
                             title: "2023-05 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5026372)".to_string(),
-                            kb: "5026372".to_string(),
-                            id: "3cf3be77-f086-449f-8ba5-033f605c688a".to_string(),
+                            kb: Some("5026372".to_string()),
+                            id: "3cf3be77-f086-449f-8ba5-033f605c688a".parse().expect("Failed to parse update id"),
+                            release_month: NaiveDate::from_ymd_opt(2023, 5, 1),
                         },
                         SupersededByUpdate {
                             title: "2023-07 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5028254)".to_string(),
-                            kb: "5028254".to_string(),
-                            id: "dbf7dc02-70ef-4476-b228-00a130a39ccd".to_string(),
+                            kb: Some("5028254".to_string()),
+                            id: "dbf7dc02-70ef-4476-b228-00a130a39ccd".parse().expect("Failed to parse update id"),
+                            release_month: NaiveDate::from_ymd_opt(2023, 7, 1),
                         },
                         SupersededByUpdate {
                             title: "2023-06 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5027303)".to_string(),
-                            kb: "5027303".to_string(),
-                            id: "e0c1bca2-82c9-4eca-b0b2-5c5a507a683a".to_string(),
+                            kb: Some("5027303".to_string()),
+                            id: "e0c1bca2-82c9-4eca-b0b2-5c5a507a683a".parse().expect("Failed to parse update id"),
+                            release_month: NaiveDate::from_ymd_opt(2023, 6, 1),
                         },
                         SupersededByUpdate {
                             title: "2023-06 Cumulative Update for Windows 11 Version 22H2 for x64-based Systems (KB5027231)".to_string(),
-                            kb: "5027231".to_string(),
-                            id: "eac58b58-fb7d-4cd4-a78a-a39f87e0f232".to_string(),
+                            kb: Some("5027231".to_string()),
+                            id: "eac58b58-fb7d-4cd4-a78a-a39f87e0f232".parse().expect("Failed to parse update id"),
+                            release_month: NaiveDate::from_ymd_opt(2023, 6, 1),
                         },
                         SupersededByUpdate {
                             title: "2023-05 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5026446)".to_string(),
-                            kb: "5026446".to_string(),
-                            id: "ec3769c8-2cd5-4e89-a0a3-6e7830c38f6f".to_string(),
+                            kb: Some("5026446".to_string()),
+                            id: "ec3769c8-2cd5-4e89-a0a3-6e7830c38f6f".parse().expect("Failed to parse update id"),
+                            release_month: NaiveDate::from_ymd_opt(2023, 5, 1),
                         },
                     ],
+                    superseded_by_truncated: false,
                 }
             ),
             (
                 load_test_data!("msuc_update_details_never_restarts.html"),
                 Update {
                     title: "Security Update For Exchange Server 2019 CU12 (KB5030524)".to_string(),
-                    id: "56a97db8-1478-4860-a935-7996c78d10be".to_string(),
+                    search_title: None,
+                    id: "56a97db8-1478-4860-a935-7996c78d10be".parse().expect("Failed to parse update id"),
                     kb: "5030524".to_string(),
+                    kb_numbers: vec!["5030524".to_string()],
                     classification: "Security Updates".to_string(),
                     last_modified: NaiveDate::from_ymd_opt(2023, 8, 15).expect("Failed to parse date for test data"),
+                    last_modified_raw: "8/15/2023".to_string(),
                     size: 168715878,
                     description: "The security update addresses the vulnerabilities descripted in the CVEs".to_string(),
+                    description_links: vec![],
                     architecture: None,
                     supported_products: vec!["Exchange Server 2019".to_string()],
+                    operating_systems: vec![],
                     supported_languages: vec!["Arabic".to_string(), "Bulgarian".to_string(), "Chinese (Traditional)".to_string(), "Czech".to_string(), "Danish".to_string(), "German".to_string(), "Greek".to_string(), "English".to_string(), "Spanish".to_string(), "Finnish".to_string(), "French".to_string(), "Hebrew".to_string(), "Hungarian".to_string(), "Italian".to_string(), "Japanese".to_string(), "Korean".to_string(), "Dutch".to_string(), "Norwegian".to_string(), "Polish".to_string(), "Portuguese (Brazil)".to_string(), "Romanian".to_string(), "Russian".to_string(), "Croatian".to_string(), "Slovak".to_string(), "Swedish".to_string(), "Thai".to_string(), "Turkish".to_string(), "Ukrainian".to_string(), "Slovenian".to_string(), "Estonian".to_string(), "Latvian".to_string(), "Lithuanian".to_string(), "Hindi".to_string(), "Chinese (Simplified)".to_string(), "Portuguese (Portugal)".to_string(), "Serbian (Latin)".to_string(), "Chinese - Hong Kong SAR".to_string(), "Japanese NEC".to_string()],
                     msrc_number: None,
                     msrc_severity: None,
+                    kb_article_url: None,
+                    security_bulletin_url: None,
                     info_url: Url::parse("https://techcommunity.microsoft.com/t5/exchange-team-blog/bg-p/Exchange").expect("Failed to parse URL for test data"),
+                    more_info_urls: vec![Url::parse("https://techcommunity.microsoft.com/t5/exchange-team-blog/bg-p/Exchange").expect("Failed to parse URL for test data")],
                     support_url: Url::parse("https://technet.microsoft.com/en-us/exchange/fp179701").expect("Failed to parse URL for test data"),
+                    support_urls: vec![Url::parse("https://technet.microsoft.com/en-us/exchange/fp179701").expect("Failed to parse URL for test data")],
                     reboot_behavior: RebootBehavior::NeverRestarts,
+                    reboot_behaviors: vec![RebootBehavior::NeverRestarts],
                     requires_user_input: false,
                     is_exclusive_install: false,
                     requires_network_connectivity: false,
                     uninstall_notes: Some("This software update can be removed via Add or Remove Programs in Control Panel.".to_string()),
                     uninstall_steps: None,
+                    driver_info: None,
                     supersedes: vec![
                         SupersedesUpdate {
                             title: "Security Update For Exchange Server 2019 CU12 (KB5026261)".to_string(),
-                            kb: "5026261".to_string(),
+                            kb: Some("5026261".to_string()),
                         },
                         SupersedesUpdate {
                             title: "Security Update For Exchange Server 2019 CU12 (KB5024296)".to_string(),
-                            kb: "5024296".to_string(),
+                            kb: Some("5024296".to_string()),
                         }],
+                    supersedes_truncated: false,
                     superseded_by: vec![],
+                    superseded_by_truncated: false,
                 }
             )
         ];
@@ -956,4 +1592,281 @@ mod test {
             assert_eq!(tc.1, res);
         }
     }
+
+    #[test]
+    fn test_parse_update_details_multiple_kbs() {
+        let html = load_test_data!("msuc_update_details.html").replace(
+            "2023-04 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5025305)</span>",
+            "2023-04 Servicing Stack Update and Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5025305) (KB5025306)</span>",
+        );
+        let res = parse_update_details(&html);
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!("5025305", res.kb);
+        assert_eq!(vec!["5025305".to_string(), "5025306".to_string()], res.kb_numbers);
+    }
+
+    #[test]
+    fn test_parse_update_details_combined_reboot_behavior() {
+        let res = parse_update_details(&load_test_data!("msuc_update_details_combined_reboot.html"));
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!(RebootBehavior::CanRequest, res.reboot_behavior);
+        assert_eq!(vec![RebootBehavior::CanRequest, RebootBehavior::Recommended], res.reboot_behaviors);
+    }
+
+    #[test]
+    fn test_parse_update_details_links() {
+        let res = parse_update_details(&load_test_data!("msuc_update_details_linked.html"));
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!("5025305", res.kb);
+        assert_eq!(Some("MS23-025".to_string()), res.msrc_number);
+        assert_eq!(
+            Some(Url::parse("https://support.microsoft.com/help/5025305").expect("Failed to parse URL for test data")),
+            res.kb_article_url
+        );
+        assert_eq!(
+            Some(Url::parse("https://www.catalog.update.microsoft.com/Security/Bulletins/MS23-025.aspx").expect("Failed to parse URL for test data")),
+            res.security_bulletin_url
+        );
+        assert!(res.description.contains("see the associated Microsoft Knowledge Base article for more information"));
+        assert_eq!(
+            vec![
+                Url::parse("https://support.microsoft.com/help/5025305").expect("Failed to parse URL for test data"),
+                Url::parse("https://www.catalog.update.microsoft.com/KnownIssues.aspx?q=5025305").expect("Failed to parse URL for test data"),
+            ],
+            res.description_links
+        );
+        assert_eq!(
+            Url::parse("https://support.microsoft.com/help/5025305").expect("Failed to parse URL for test data"),
+            res.info_url
+        );
+        assert_eq!(
+            vec![
+                Url::parse("https://support.microsoft.com/help/5025305").expect("Failed to parse URL for test data"),
+                Url::parse("https://www.catalog.update.microsoft.com/Blogs/Windows11-22H2-April-Update.aspx").expect("Failed to parse URL for test data"),
+            ],
+            res.more_info_urls
+        );
+    }
+
+    #[test]
+    fn test_parse_update_details_fallback_selectors() {
+        // the ids of the fields themselves are changed, leaving only their containers to match
+        let html = load_test_data!("msuc_update_details.html")
+            .replace(r#"id="ScopedViewHandler_titleText""#, r#"id="ScopedViewHandler_title""#)
+            .replace(r#"id="ScopedViewHandler_date""#, r#"id="ScopedViewHandler_lastModified""#)
+            .replace(r#"id="ScopedViewHandler_size""#, r#"id="ScopedViewHandler_sizeText""#)
+            .replace(r#"id="ScopedViewHandler_UpdateID""#, r#"id="ScopedViewHandler_updateId""#);
+        let expected = parse_update_details(&load_test_data!("msuc_update_details.html"))
+            .expect("Failed to parse update details");
+        let res = parse_update_details(&html).expect("Failed to parse update details");
+        assert_eq!(expected, res);
+        let summary = parse_update_summary(&html).expect("Failed to parse update summary");
+        assert_eq!(expected.title, summary.title);
+        assert_eq!(expected.id, summary.id);
+        assert_eq!(expected.last_modified, summary.last_modified);
+        assert_eq!(expected.size, summary.size);
+    }
+
+    #[test]
+    fn test_parse_update_details_support_urls() {
+        // the container id is spelled without the catalog's typo, which is matched as a fallback
+        let html = load_test_data!("msuc_update_details.html")
+            .replace(r#"id="suportUrlDiv""#, r#"id="supportUrlDiv""#)
+            .replace(
+                "Support Url:</span>",
+                r#"Support Url:</span><div><a href="https://support.microsoft.com/windows">https://support.microsoft.com/windows</a></div>"#,
+            );
+        let res = parse_update_details(&html).expect("Failed to parse update details");
+        assert_eq!(
+            Url::parse("https://support.microsoft.com/windows").expect("Failed to parse URL for test data"),
+            res.support_url
+        );
+        assert_eq!(
+            vec![
+                Url::parse("https://support.microsoft.com/windows").expect("Failed to parse URL for test data"),
+                Url::parse("https://support.microsoft.com/help/5025305").expect("Failed to parse URL for test data"),
+            ],
+            res.support_urls
+        );
+    }
+
+    #[test]
+    fn test_parse_update_details_truncated_supersedence() {
+        let complete = parse_update_details(&load_test_data!("msuc_update_details.html"))
+            .expect("Failed to parse update details");
+        // the catalog ends a truncated list with a link or note in place of the remaining updates
+        let html = load_test_data!("msuc_update_details.html")
+            .replace(
+                "(KB5026446)</a>",
+                r#"(KB5026446)</a></div><div style="padding-bottom: 0.3em;"><a href="javascript:void(0);">See all 12 updates</a>"#,
+            )
+            .replace("(KB5023706)", r#"(KB5023706)</div><div style="padding-bottom: 0.3em;">and 14 more..."#);
+        let res = parse_update_details(&html).expect("Failed to parse update details");
+        assert!(!complete.supersedes_truncated);
+        assert!(!complete.superseded_by_truncated);
+        assert!(res.supersedes_truncated);
+        assert!(res.superseded_by_truncated);
+        assert_eq!(complete.supersedes, res.supersedes);
+        assert_eq!(complete.superseded_by, res.superseded_by);
+    }
+
+    #[test]
+    fn test_parse_update_details_no_size() {
+        let html = load_test_data!("msuc_update_details.html").replace(
+            r#"<span id="ScopedViewHandler_size">316.2 MB</span>"#,
+            r#"<span id="ScopedViewHandler_size"></span>"#,
+        );
+        let res = parse_update_details(&html).expect("Failed to parse update details");
+        assert_eq!(0, res.size);
+        assert!(res.validate().is_err());
+        assert_eq!(Some(0), parse_size_from_mb_string("n/a".to_string()).ok());
+        assert_eq!(Some(331559731), parse_size_from_mb_string("316.2 MB".to_string()).ok());
+    }
+
+    #[test]
+    fn test_parse_update_details_driver() {
+        let res = parse_update_details(&load_test_data!("msuc_update_details_driver.html"))
+            .expect("Failed to parse update details");
+        assert_eq!("Drivers", res.classification);
+        assert_eq!(
+            Some(DriverInfo {
+                version: "31.0.101.4669".to_string(),
+                date: NaiveDate::from_ymd_opt(2023, 8, 21).expect("Failed to create date"),
+                provider: "Intel Corporation".to_string(),
+                class: "Display".to_string(),
+            }),
+            res.driver_info
+        );
+    }
+
+    #[test]
+    fn test_parse_update_details_empty_lists() {
+        let res = parse_update_details(&load_test_data!("msuc_update_details_empty_lists.html"))
+            .expect("Failed to parse update details");
+        assert!(res.supported_products.is_empty());
+        assert!(res.supported_languages.is_empty());
+    }
+
+    #[test]
+    fn test_parse_update_details_operating_systems() {
+        // the products list is split into products and the operating systems they run on
+        let html = load_test_data!("msuc_update_details.html")
+            .replace(
+                "Supported products:</span>",
+                concat!(
+                    "Supported products:</span>\nOffice 2016\n</div>\n",
+                    r#"<div id="operatingSystemsDiv"><span id="ScopedViewHandler_labelOperatingSystems_Separator" class="labelTitle">Operating systems:</span>"#,
+                    "\n",
+                ),
+            )
+            .replace("Windows 11\n", "Windows 11\n,\nWindows Server 2022\n");
+        let res = parse_update_details(&html).expect("Failed to parse update details");
+        assert_eq!(vec!["Office 2016".to_string()], res.supported_products);
+        assert_eq!(
+            vec!["Windows 11".to_string(), "Windows Server 2022".to_string()],
+            res.operating_systems
+        );
+        let res = parse_update_details(&load_test_data!("msuc_update_details.html"))
+            .expect("Failed to parse update details");
+        assert!(res.operating_systems.is_empty());
+    }
+
+    #[test]
+    fn test_parse_update_fields() {
+        let html = load_test_data!("msuc_update_details.html");
+        let update = parse_update_details(&html).expect("Failed to parse update details");
+        let res = parse_update_fields(&html, UpdateFields::KB | UpdateFields::SIZE | UpdateFields::SUPERSEDED_BY)
+            .expect("Failed to parse update fields");
+        assert_eq!(
+            PartialUpdate {
+                kb: Some(update.kb),
+                size: Some(update.size),
+                superseded_by: Some(update.superseded_by),
+                superseded_by_truncated: Some(false),
+                ..Default::default()
+            },
+            res
+        );
+
+        // the more information link can't be parsed, but it isn't selected
+        let html = html.replace(
+            "https://support.microsoft.com/help/5025305\n                                        </a>",
+            "not a url\n                                        </a>",
+        );
+        assert!(parse_update_details(&html).is_err());
+        assert!(parse_update_fields(&html, UpdateFields::ALL).is_ok());
+    }
+
+    #[test]
+    fn test_parse_hidden_error_update_details() {
+        let test_cases = [(
+            load_test_data!("msuc_update_details_error_500.html"),
+            "Microsoft Update Catalog error: received 500 error from Microsoft Update Catalog, code: 8DDD0010",
+        )];
+
+        for tc in test_cases.iter() {
+            match parse_update_details(tc.0.as_str()) {
+                Err(e @ Error::Msuc(_, _)) => {
+                    assert_eq!(tc.1, e.to_string());
+                }
+                _ => {
+                    panic!("Expected Msuc error to be returned");
+                }
+            }
+            match parse_update_summary(tc.0.as_str()) {
+                Err(e @ Error::Msuc(_, _)) => {
+                    assert_eq!(tc.1, e.to_string());
+                }
+                _ => {
+                    panic!("Expected Msuc error to be returned");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_update_date_error_includes_raw_value() {
+        let res = parse_update_date("2023-08-15T00:00:00".to_string());
+        match res {
+            Err(Error::Parsing(msg)) => assert!(msg.contains("'2023-08-15T00:00:00'")),
+            _ => panic!("Expected parsing error to be returned"),
+        }
+    }
+
+    #[test]
+    fn test_parse_update_summary() {
+        let test_cases = [
+            (
+                load_test_data!("msuc_update_details.html"),
+                UpdateSummary {
+                    title: "2023-04 Cumulative Update Preview for Windows 11 Version 22H2 for x64-based Systems (KB5025305)".to_string(),
+                    id: "1b0b70c0-191e-42f6-8808-c1b50deacb3b".parse().expect("Failed to parse update id"),
+                    kb: "5025305".to_string(),
+                    classification: "Updates".to_string(),
+                    last_modified: NaiveDate::from_ymd_opt(2023, 4, 25).expect("Failed to parse date for test data"),
+                    size: 331559731,
+                }
+            ),
+            (
+                load_test_data!("msuc_update_details_never_restarts.html"),
+                UpdateSummary {
+                    title: "Security Update For Exchange Server 2019 CU12 (KB5030524)".to_string(),
+                    id: "56a97db8-1478-4860-a935-7996c78d10be".parse().expect("Failed to parse update id"),
+                    kb: "5030524".to_string(),
+                    classification: "Security Updates".to_string(),
+                    last_modified: NaiveDate::from_ymd_opt(2023, 8, 15).expect("Failed to parse date for test data"),
+                    size: 168715878,
+                }
+            )
+        ];
+        for tc in test_cases.iter() {
+            let res = parse_update_summary(&tc.0);
+            assert!(res.is_ok());
+            let res = res.unwrap();
+            assert_eq!(tc.1, res);
+        }
+    }
 }
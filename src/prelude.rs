@@ -1,2 +1,10 @@
+pub use crate::client::Catalog;
 pub use crate::client::Client as MsucClient;
-pub use crate::client::SearchResultsStreamer;
\ No newline at end of file
+pub use crate::client::ClientConfig as MsucClientConfig;
+pub use crate::client::ClientBuilder as MsucClientBuilder;
+pub use crate::client::ResponseHook;
+pub use crate::client::SearchResultsStreamer;
+pub use crate::model::{compare_kb, diff_search_results, DriverInfo, PartialUpdate, RebootBehavior, RequestStats, ResolvedDownload, ResponseInfo, SearchDiff, SearchFormFields, SearchQuery, SearchResColumn, Timing, UpdateDiff, UpdateField, UpdateFields, UpdateId, UpdateSummary};
+#[cfg(feature = "csv")]
+pub use crate::model::export_wsus_csv;
+pub use crate::parser::{parse_search_files, parse_search_results_only};
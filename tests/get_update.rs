@@ -5,7 +5,8 @@ use msuc::prelude::*;
 async fn test_get_update() {
     let client = MsucClient::new().expect("failed to create client");
     // MS08-067: KB958644
-    let details = client.get_update("9602ca4a-80a7-4d73-94c3-0088fcb5bce3").await;
+    let update_id: UpdateId = "9602ca4a-80a7-4d73-94c3-0088fcb5bce3".parse().expect("failed to parse update id");
+    let details = client.get_update(&update_id).await;
     assert!(details.is_ok(), "expected get_update call to succeed");
     let details = details.unwrap();
     assert_eq!(details.title, "Security Update for Windows XP x64 Edition (KB958644)");
@@ -17,9 +18,54 @@ async fn test_get_update() {
 fn test_get_update() {
     let client = MsucClient::new().expect("failed to create client");
     // MS08-067: KB958644
-    let details = client.get_update("9602ca4a-80a7-4d73-94c3-0088fcb5bce3");
+    let update_id: UpdateId = "9602ca4a-80a7-4d73-94c3-0088fcb5bce3".parse().expect("failed to parse update id");
+    let details = client.get_update(&update_id);
     assert!(details.is_ok(), "expected get_update call to succeed");
     let details = details.unwrap();
     assert_eq!(details.title, "Security Update for Windows XP x64 Edition (KB958644)");
     assert_eq!(details.id, "9602ca4a-80a7-4d73-94c3-0088fcb5bce3");
-}
\ No newline at end of file
+}
+
+#[cfg(not(feature = "blocking"))]
+#[tokio::test]
+async fn test_summarize() {
+    let client = MsucClient::new().expect("failed to create client");
+    // MS08-067: KB958644
+    let update_id: UpdateId = "9602ca4a-80a7-4d73-94c3-0088fcb5bce3".parse().expect("failed to parse update id");
+    let summary = client.summarize(&update_id).await;
+    assert!(summary.is_ok(), "expected summarize call to succeed");
+    let summary = summary.unwrap();
+    assert_eq!(summary.title, "Security Update for Windows XP x64 Edition (KB958644)");
+    assert_eq!(summary.id, "9602ca4a-80a7-4d73-94c3-0088fcb5bce3");
+    assert_eq!(summary.kb, "958644");
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_summarize() {
+    let client = MsucClient::new().expect("failed to create client");
+    // MS08-067: KB958644
+    let update_id: UpdateId = "9602ca4a-80a7-4d73-94c3-0088fcb5bce3".parse().expect("failed to parse update id");
+    let summary = client.summarize(&update_id);
+    assert!(summary.is_ok(), "expected summarize call to succeed");
+    let summary = summary.unwrap();
+    assert_eq!(summary.title, "Security Update for Windows XP x64 Edition (KB958644)");
+    assert_eq!(summary.id, "9602ca4a-80a7-4d73-94c3-0088fcb5bce3");
+    assert_eq!(summary.kb, "958644");
+}
+
+#[cfg(not(feature = "blocking"))]
+#[tokio::test]
+async fn test_self_test() {
+    let client = MsucClient::new().expect("failed to create client");
+    let res = client.self_test().await;
+    assert!(res.is_ok(), "expected self test to succeed: {:?}", res);
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_self_test() {
+    let client = MsucClient::new().expect("failed to create client");
+    let res = client.self_test();
+    assert!(res.is_ok(), "expected self test to succeed: {:?}", res);
+}
@@ -201,3 +201,35 @@ fn test_msuc_client_search_stream_too_many_results() {
         },
     }
 }
+
+#[cfg(not(feature = "blocking"))]
+#[tokio::test]
+async fn test_msuc_client_search_stream_peek_next() {
+    let client = MsucClient::new().expect("Client creation failed");
+    let mut stream = client.search("ms08-067").expect("Failed to create search stream");
+    let peeked = stream.peek_next().await;
+    assert!(peeked.is_ok(), "Expected the peeked page to be Ok");
+    let peeked = peeked.unwrap();
+    assert!(peeked.is_some(), "Expected the peeked page to contain search results");
+
+    // The next page should be the buffered page rather than a new request
+    let page = stream.next().await;
+    assert!(page.is_ok(), "Expected the next page to be Ok");
+    assert_eq!(peeked, page.unwrap(), "Expected next to return the peeked page");
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_msuc_client_search_stream_peek_next() {
+    let client = MsucClient::new().expect("Client creation failed");
+    let mut stream = client.search("ms08-067").expect("Failed to create search stream");
+    let peeked = stream.peek_next();
+    assert!(peeked.is_ok(), "Expected the peeked page to be Ok");
+    let peeked = peeked.unwrap();
+    assert!(peeked.is_some(), "Expected the peeked page to contain search results");
+
+    // The next page should be the buffered page rather than a new request
+    let page = stream.next();
+    assert!(page.is_ok(), "Expected the next page to be Ok");
+    assert_eq!(peeked, page.unwrap(), "Expected next to return the peeked page");
+}